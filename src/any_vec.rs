@@ -1,43 +1,130 @@
+use crate::EcsError;
 use std::{
-    alloc::{alloc, handle_alloc_error, realloc, Layout},
+    alloc::{alloc, dealloc, handle_alloc_error, realloc, Layout},
     mem::ManuallyDrop,
-    ptr::{copy_nonoverlapping, read, NonNull},
+    ptr::{copy, copy_nonoverlapping, drop_in_place, read, NonNull},
+    slice,
 };
 
+/// Drops the element stored at the given pointer in place.
+pub type DropFn = unsafe fn(*mut u8);
+
+/// Type-erased [`DropFn`] for `T`.
+///
+/// # Safety
+/// `ptr` must point to a valid, initialized `T`.
+pub unsafe fn drop_ptr<T>(ptr: *mut u8) {
+    unsafe { drop_in_place(ptr.cast::<T>()) }
+}
+
 #[derive(Debug)]
 pub struct AnyVec {
     ptr: NonNull<u8>,
     layout: Layout,
     len: usize,
     cap: usize,
+    drop: Option<DropFn>,
 }
 
 impl AnyVec {
     pub fn new(layout: Layout) -> Self {
+        Self::with_drop(layout, None)
+    }
+
+    /// Creates an empty vector whose elements are dropped with `drop` when they are removed or
+    /// when the vector itself is dropped.
+    pub fn with_drop(layout: Layout, drop: Option<DropFn>) -> Self {
         Self {
-            ptr: NonNull::dangling(),
+            ptr: Self::dangling(layout),
             len: 0,
-            cap: 0,
+            cap: if layout.size() == 0 { usize::MAX } else { 0 },
             layout,
+            drop,
+        }
+    }
+
+    pub fn with_capacity(layout: Layout, capacity: usize) -> Self {
+        let mut any_vec = Self::new(layout);
+        any_vec.reserve(capacity);
+        any_vec
+    }
+
+    /// Creates an empty vector that stores the same kind of elements as `self`.
+    pub fn empty_like(&self) -> Self {
+        Self::with_drop(self.layout, self.drop)
+    }
+
+    fn dangling(layout: Layout) -> NonNull<u8> {
+        // Zero sized and unallocated elements still need a well aligned pointer.
+        NonNull::new(layout.align() as *mut u8).unwrap()
+    }
+
+    /// Makes room for at least `additional` more elements in a single allocation.
+    pub fn reserve(&mut self, additional: usize) {
+        let required = self.len.checked_add(additional).expect("capacity overflow");
+
+        if required > self.cap {
+            self.grow_to(required.max(self.cap * 2));
         }
     }
 
+    /// Like [`AnyVec::reserve`], but reports running out of memory instead of aborting.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), EcsError> {
+        let required = self
+            .len
+            .checked_add(additional)
+            .ok_or(EcsError::AllocationFailed)?;
+
+        if required > self.cap {
+            self.try_grow_to(required.max(self.cap * 2))?;
+        }
+
+        Ok(())
+    }
+
     fn grow(&mut self) {
-        let (new_cap, new_layout) = if self.cap == 0 {
-            (1, self.layout)
-        } else {
-            (
-                self.cap * 2,
-                Layout::from_size_align(self.layout.size() * self.cap * 2, self.layout.align())
-                    .unwrap(),
-            )
-        };
+        self.grow_to(if self.cap == 0 { 1 } else { self.cap * 2 });
+    }
+
+    fn grow_to(&mut self, new_cap: usize) {
+        let new_size = self
+            .layout
+            .size()
+            .checked_mul(new_cap)
+            .expect("capacity overflow");
+        let new_layout = Layout::from_size_align(new_size, self.layout.align()).unwrap();
 
         assert!(
             new_layout.size() <= isize::MAX as usize,
             "Allocation too large"
         );
 
+        if !unsafe { self.reallocate(new_cap, new_layout) } {
+            handle_alloc_error(new_layout);
+        }
+    }
+
+    fn try_grow_to(&mut self, new_cap: usize) -> Result<(), EcsError> {
+        let new_layout = self
+            .layout
+            .size()
+            .checked_mul(new_cap)
+            .and_then(|new_size| Layout::from_size_align(new_size, self.layout.align()).ok())
+            .ok_or(EcsError::AllocationFailed)?;
+
+        if !unsafe { self.reallocate(new_cap, new_layout) } {
+            return Err(EcsError::AllocationFailed);
+        }
+
+        Ok(())
+    }
+
+    /// Moves the elements to an allocation of `new_layout`, returning false and leaving them
+    /// where they are if it can't be made.
+    ///
+    /// # Safety
+    /// `new_layout` must hold `new_cap` elements and be no larger than `isize::MAX` bytes.
+    unsafe fn reallocate(&mut self, new_cap: usize, new_layout: Layout) -> bool {
         let new_ptr = if self.cap == 0 {
             unsafe { alloc(new_layout) }
         } else {
@@ -47,11 +134,13 @@ impl AnyVec {
             unsafe { realloc(self.ptr.as_ptr(), old_layout, new_layout.size()) }
         };
 
+        let Some(new_ptr) = NonNull::new(new_ptr) else {
+            return false;
+        };
+
         self.cap = new_cap;
-        self.ptr = match NonNull::new(new_ptr) {
-            Some(ptr) => ptr,
-            None => handle_alloc_error(new_layout),
-        }
+        self.ptr = new_ptr;
+        true
     }
 
     /// # Safety
@@ -127,6 +216,151 @@ impl AnyVec {
         Some(unsafe { &mut *ptr })
     }
 
+    /// Moves the last element into `index` without dropping the element that was there.
+    ///
+    /// # Safety
+    /// The element at `index` must have been moved out (e.g. with [`AnyVec::push_raw`] into
+    /// another vector) or otherwise be safe to forget.
+    pub unsafe fn swap_remove_forget(&mut self, index: usize) {
+        assert!(index < self.len, "index out of bounds");
+
+        let last = self.len - 1;
+        if index != last {
+            unsafe {
+                let size = self.layout.size();
+                copy_nonoverlapping(
+                    self.ptr.as_ptr().add(last * size),
+                    self.ptr.as_ptr().add(index * size),
+                    size,
+                );
+            }
+        }
+
+        self.len -= 1;
+    }
+
+    /// Drops the element at `index` and moves the last element into its place.
+    pub fn swap_remove_drop(&mut self, index: usize) {
+        assert!(index < self.len, "index out of bounds");
+
+        if let Some(drop) = self.drop {
+            unsafe { drop(self.ptr.as_ptr().add(index * self.layout.size())) };
+        }
+
+        unsafe { self.swap_remove_forget(index) };
+    }
+
+    /// Moves the element at `index` out of the vector, replacing it with the last element.
+    pub fn swap_remove<T>(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+
+        let element =
+            unsafe { read(self.ptr.as_ptr().add(index * self.layout.size()) as *const T) };
+        unsafe { self.swap_remove_forget(index) };
+
+        Some(element)
+    }
+
+    /// Drops the element at `index` and moves the bytes at `bytes` into its place.
+    ///
+    /// # Safety
+    /// `bytes` must point to a valid element with the layout of this vector, which is moved
+    /// into the vector and must not be used or dropped by the caller afterwards.
+    pub unsafe fn replace_raw(&mut self, index: usize, bytes: *const u8) {
+        assert!(index < self.len, "index out of bounds");
+
+        let dst = unsafe { self.ptr.as_ptr().add(index * self.layout.size()) };
+
+        if let Some(drop) = self.drop {
+            unsafe { drop(dst) };
+        }
+
+        unsafe { copy(bytes, dst, self.layout.size()) };
+    }
+
+    /// Moves every element of `other` to the end of `self` with a single copy, leaving `other`
+    /// empty.
+    pub fn append(&mut self, other: &mut AnyVec) {
+        assert_eq!(self.layout, other.layout, "elements of different layouts");
+        self.reserve(other.len);
+
+        unsafe {
+            copy_nonoverlapping(
+                other.ptr.as_ptr(),
+                self.ptr.as_ptr().add(self.len * self.layout.size()),
+                other.len * self.layout.size(),
+            );
+        }
+
+        self.len += other.len;
+        other.len = 0;
+    }
+
+    /// Drops every element, keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        let len = self.len;
+        self.len = 0;
+
+        if let Some(drop) = self.drop {
+            (0..len).for_each(|index| unsafe {
+                drop(self.ptr.as_ptr().add(index * self.layout.size()))
+            });
+        }
+    }
+
+    /// Shrinks the allocation to fit the current elements, freeing it entirely if there are
+    /// none.
+    pub fn shrink_to_fit(&mut self) {
+        let size = self.layout.size();
+        if size == 0 || self.cap == self.len {
+            return;
+        }
+
+        let old_layout = Layout::from_size_align(size * self.cap, self.layout.align()).unwrap();
+
+        if self.len == 0 {
+            unsafe { dealloc(self.ptr.as_ptr(), old_layout) };
+            self.ptr = Self::dangling(self.layout);
+            self.cap = 0;
+            return;
+        }
+
+        let new_layout = Layout::from_size_align(size * self.len, self.layout.align()).unwrap();
+        let new_ptr = unsafe { realloc(self.ptr.as_ptr(), old_layout, new_layout.size()) };
+
+        self.cap = self.len;
+        self.ptr = match NonNull::new(new_ptr) {
+            Some(ptr) => ptr,
+            None => handle_alloc_error(new_layout),
+        }
+    }
+
+    /// # Safety
+    /// Elements past `len` are forgotten, and `len` must not exceed the initialized elements.
+    pub unsafe fn set_len(&mut self, len: usize) {
+        self.len = len;
+    }
+
+    /// Views the elements as a slice of `T`, or `None` if `T` doesn't match the layout.
+    pub fn as_slice<T>(&self) -> Option<&[T]> {
+        if Layout::new::<T>() != self.layout {
+            return None;
+        }
+
+        Some(unsafe { slice::from_raw_parts(self.ptr.as_ptr().cast::<T>(), self.len) })
+    }
+
+    /// Views the elements as a mutable slice of `T`, or `None` if `T` doesn't match the layout.
+    pub fn as_mut_slice<T>(&mut self) -> Option<&mut [T]> {
+        if Layout::new::<T>() != self.layout {
+            return None;
+        }
+
+        Some(unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr().cast::<T>(), self.len) })
+    }
+
     pub fn len(&self) -> usize {
         self.len
     }
@@ -135,6 +369,21 @@ impl AnyVec {
         self.len == 0
     }
 
+    /// How many elements fit before the vector has to grow. Like `Vec`, zero sized elements
+    /// never need to grow, so their capacity is `usize::MAX`.
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// The size of the heap allocation backing the vector.
+    pub fn allocated_bytes(&self) -> usize {
+        if self.layout.size() == 0 {
+            return 0;
+        }
+
+        self.layout.size() * self.cap
+    }
+
     pub fn layout(&self) -> Layout {
         self.layout
     }
@@ -147,3 +396,16 @@ impl AnyVec {
         self.get(0)
     }
 }
+
+impl Drop for AnyVec {
+    fn drop(&mut self) {
+        self.clear();
+
+        if self.layout.size() != 0 && self.cap != 0 {
+            let layout =
+                Layout::from_size_align(self.layout.size() * self.cap, self.layout.align())
+                    .unwrap();
+            unsafe { dealloc(self.ptr.as_ptr(), layout) };
+        }
+    }
+}
@@ -1,19 +1,16 @@
-use raylib::color::Color;
-
-use super::{rail::Rail, tile::Tile};
+use super::{grid_position::GridPosition, rail::Rail, tile::Tile};
 
 #[derive(Debug)]
 pub struct Ghost {
     pub selected_tile: Tile,
+    pub position: GridPosition,
 }
 
 impl Default for Ghost {
     fn default() -> Self {
         Self {
-            selected_tile: Tile::Rail(Rail {
-                color: Color::BLACK.alpha(0.5),
-                ..Default::default()
-            }),
+            selected_tile: Tile::Rail(Rail::default()),
+            position: GridPosition::default(),
         }
     }
 }
@@ -0,0 +1,5 @@
+use raylib::color::Color;
+
+/// The colour of a rail line, shared by every rail on it.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct LineColor(pub Color);
@@ -2,6 +2,7 @@ pub mod anchor;
 pub mod connections;
 pub mod ghost;
 pub mod grid_position;
+pub mod line_color;
 pub mod rail;
 pub mod station;
 pub mod tile;
@@ -11,13 +11,17 @@ use raylib::{
 
 #[derive(Default, Debug)]
 pub struct Rail {
-    pub position: GridPosition,
-    pub color: Color,
     pub connections: Connections,
 }
 
 impl Rail {
-    pub fn draw(&self, world: &World, context: &mut RaylibDrawHandle) {
+    pub fn draw(
+        &self,
+        world: &World,
+        context: &mut RaylibDrawHandle,
+        position: GridPosition,
+        color: Color,
+    ) {
         let world_width = world.get_resource::<WorldWidth>().unwrap();
         let world_height = world.get_resource::<WorldHeight>().unwrap();
         let grid_divisions = world.get_resource::<GridDivisions>().unwrap();
@@ -25,8 +29,6 @@ impl Rail {
 
         let offset = (0.25 * tile_size.0 as f32) as i32;
 
-        let position = self.position;
-
         let x = position.col as i32 * (world_width.0 / grid_divisions.0 as i32);
         let y = position.row as i32 * (world_height.0 / grid_divisions.0 as i32);
 
@@ -36,7 +38,7 @@ impl Rail {
             y + offset,
             tile_size.0 - offset * 2,
             tile_size.0 - offset * 2,
-            self.color,
+            color,
         );
 
         // Draw connections
@@ -47,12 +49,12 @@ impl Rail {
                     y + tile_size.0 - offset,
                     tile_size.0 - offset * 2,
                     offset,
-                    self.color,
+                    color,
                 );
             }
 
             if connection.row as i32 == position.row as i32 - 1 {
-                context.draw_rectangle(x + offset, y, tile_size.0 - offset * 2, offset, self.color);
+                context.draw_rectangle(x + offset, y, tile_size.0 - offset * 2, offset, color);
             }
 
             if connection.col as i32 == position.col as i32 + 1 {
@@ -61,12 +63,12 @@ impl Rail {
                     y + offset,
                     offset,
                     tile_size.0 - offset * 2,
-                    self.color,
+                    color,
                 );
             }
 
             if connection.col as i32 == position.col as i32 - 1 {
-                context.draw_rectangle(x, y + offset, offset, tile_size.0 - offset * 2, self.color);
+                context.draw_rectangle(x, y + offset, offset, tile_size.0 - offset * 2, color);
             }
         }
     }
@@ -11,7 +11,6 @@ use raylib::{
 
 #[derive(Default, Debug)]
 pub struct Station {
-    pub position: GridPosition,
     pub color: Color,
     pub connections: Connections,
     pub connections_color: Color,
@@ -19,7 +18,7 @@ pub struct Station {
 }
 
 impl Station {
-    pub fn draw(&self, world: &World, context: &mut RaylibDrawHandle) {
+    pub fn draw(&self, world: &World, context: &mut RaylibDrawHandle, position: GridPosition) {
         let world_width = world.get_resource::<WorldWidth>().unwrap();
         let world_height = world.get_resource::<WorldHeight>().unwrap();
         let grid_divisions = world.get_resource::<GridDivisions>().unwrap();
@@ -27,8 +26,6 @@ impl Station {
 
         let offset = (0.2 * tile_size.0 as f32) as i32;
 
-        let position = self.position;
-
         let x = position.col as i32 * (world_width.0 / grid_divisions.0 as i32);
         let y = position.row as i32 * (world_height.0 / grid_divisions.0 as i32);
 
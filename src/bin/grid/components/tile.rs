@@ -1,5 +1,5 @@
 use new_ecs::World;
-use raylib::prelude::RaylibDrawHandle;
+use raylib::{color::Color, prelude::RaylibDrawHandle};
 
 use super::{connections::Connections, grid_position::GridPosition, rail::Rail, station::Station};
 
@@ -10,24 +10,17 @@ pub enum Tile {
 }
 
 impl Tile {
-    pub fn draw(&self, world: &World, context: &mut RaylibDrawHandle) {
+    /// Draws the tile at `position`, using `line_color` for rails.
+    pub fn draw(
+        &self,
+        world: &World,
+        context: &mut RaylibDrawHandle,
+        position: GridPosition,
+        line_color: Color,
+    ) {
         match self {
-            Tile::Rail(rail) => rail.draw(world, context),
-            Tile::Station(station) => station.draw(world, context),
-        }
-    }
-
-    pub fn set_position(&mut self, position: GridPosition) {
-        match self {
-            Tile::Rail(rail) => rail.position = position,
-            Tile::Station(station) => station.position = position,
-        }
-    }
-
-    pub fn get_position(&self) -> GridPosition {
-        match self {
-            Tile::Station(station) => station.position,
-            Tile::Rail(rail) => rail.position,
+            Tile::Rail(rail) => rail.draw(world, context, position, line_color),
+            Tile::Station(station) => station.draw(world, context, position),
         }
     }
 
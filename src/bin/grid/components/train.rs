@@ -38,7 +38,7 @@ impl Direction {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Train {
     pub coordinates: Vector2,
     pub stations: Vec<GridPosition>,
@@ -4,6 +4,7 @@ use super::grid_position::GridPosition;
 use new_ecs::World;
 use raylib::prelude::*;
 
+#[derive(Clone)]
 pub struct Wagon {
     pub position: GridPosition,
 }
@@ -8,7 +8,7 @@ use components::{
     anchor::Anchor, connections::Connections, ghost::Ghost, grid_position::GridPosition,
     rail::Rail, train::Train, wagon::Wagon,
 };
-use new_ecs::World;
+use new_ecs::{Prefab, World};
 use raylib::prelude::*;
 use render_systems::{
     render_ghost_system::RenderGhostSystem, render_grid_system::RenderGridSystem,
@@ -75,13 +75,13 @@ fn main() {
     let tile_size = TileSize(world_width.0 / divisions.0 as i32);
 
     let mut world = World::default();
+    world.register_index::<GridPosition>();
 
     {
         world.add_resource(world_width);
         world.add_resource(world_height);
         world.add_resource(divisions);
         world.add_resource(tile_size);
-        world.add_resource(tile_size);
     }
 
     {
@@ -90,71 +90,39 @@ fn main() {
             .with_component(Ghost::default())
             .spawn();
 
-        let coordinates = Transformer::coordinate(&world, GridPosition::new(1, 1), Anchor::Center);
-        world
-            .create_entity()
-            .with_component(Train {
-                coordinates,
-                stations: vec![
-                    GridPosition::new(4, 4),
-                    GridPosition::new(0, 0),
-                    GridPosition::new(5, 5),
-                ],
-                wagons: vec![
-                    Wagon {
-                        position: GridPosition::new(2, 1),
-                    },
-                    Wagon {
-                        position: GridPosition::new(3, 1),
-                    },
-                ],
-                ..Default::default()
-            })
-            .spawn();
-
-        let coordinates = Transformer::coordinate(&world, GridPosition::new(2, 2), Anchor::Center);
-        world
-            .create_entity()
-            .with_component(Train {
-                coordinates,
-                stations: vec![
-                    GridPosition::new(5, 5),
-                    GridPosition::new(1, 1),
-                    GridPosition::new(6, 6),
-                ],
-                wagons: vec![
-                    Wagon {
-                        position: GridPosition::new(3, 2),
-                    },
-                    Wagon {
-                        position: GridPosition::new(4, 2),
-                    },
-                ],
-                ..Default::default()
-            })
-            .spawn();
-
-        let coordinates = Transformer::coordinate(&world, GridPosition::new(3, 3), Anchor::Center);
-        world
-            .create_entity()
-            .with_component(Train {
-                coordinates,
-                stations: vec![
-                    GridPosition::new(6, 6),
-                    GridPosition::new(2, 2),
-                    GridPosition::new(7, 7),
-                ],
-                wagons: vec![
-                    Wagon {
-                        position: GridPosition::new(4, 3),
-                    },
-                    Wagon {
-                        position: GridPosition::new(5, 3),
-                    },
-                ],
-                ..Default::default()
-            })
-            .spawn();
+        let train = Prefab::new().with_component(Train {
+            stations: vec![
+                GridPosition::new(4, 4),
+                GridPosition::new(0, 0),
+                GridPosition::new(5, 5),
+            ],
+            wagons: vec![
+                Wagon {
+                    position: GridPosition::new(2, 1),
+                },
+                Wagon {
+                    position: GridPosition::new(3, 1),
+                },
+            ],
+            ..Default::default()
+        });
+
+        // Each train runs the same route, one tile further down the diagonal than the last.
+        for offset in 0..3 {
+            let start = GridPosition::new(1 + offset, 1 + offset);
+            let coordinates = Transformer::coordinate(&world, start, Anchor::Center);
+
+            let entity = world.instantiate(&train).spawn().id;
+            let train = world.get_component_mut::<Train>(entity).unwrap();
+            train.coordinates = coordinates;
+            train
+                .stations
+                .iter_mut()
+                .chain(train.wagons.iter_mut().map(|wagon| &mut wagon.position))
+                .for_each(|position| {
+                    *position = GridPosition::new(position.row + offset, position.col + offset)
+                });
+        }
     }
 
     let mut draw_scheduler = DrawScheduler::default();
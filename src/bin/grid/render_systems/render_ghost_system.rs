@@ -1,3 +1,5 @@
+use raylib::color::Color;
+
 use crate::{schedulers::draw_scheduler::DrawSystem, Ghost};
 
 pub struct RenderGhostSystem;
@@ -8,7 +10,9 @@ impl DrawSystem for RenderGhostSystem {
 
         for result in query {
             let ghost = result.get::<Ghost>().unwrap();
-            ghost.selected_tile.draw(world, context);
+            ghost
+                .selected_tile
+                .draw(world, context, ghost.position, Color::BLACK.alpha(0.5));
         }
     }
 }
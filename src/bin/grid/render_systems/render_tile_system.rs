@@ -1,18 +1,28 @@
 use new_ecs::World;
-use raylib::prelude::RaylibDrawHandle;
+use raylib::{color::Color, prelude::RaylibDrawHandle};
 
-use crate::{components::tile::Tile, schedulers::draw_scheduler::DrawSystem};
+use crate::{
+    components::{grid_position::GridPosition, line_color::LineColor, tile::Tile},
+    schedulers::draw_scheduler::DrawSystem,
+};
 
 #[derive(Default)]
 pub struct RenderTileSystem;
 
 impl DrawSystem for RenderTileSystem {
     fn update(&self, world: &World, context: &mut RaylibDrawHandle) {
-        let query = world.query().with_component::<Tile>().iter(world);
+        let chunks = world.query().with_component::<Tile>().iter_chunks(world);
 
-        for result in query {
-            let tile = result.get::<Tile>().unwrap();
-            tile.draw(world, context);
+        // Tiles are grouped by the line they belong to, so each chunk has a single colour.
+        for chunk in chunks {
+            let line_color = chunk
+                .get_shared::<LineColor>()
+                .map_or(Color::BLACK, |line_color| line_color.0);
+
+            let positions = chunk.get::<GridPosition>().unwrap();
+            for (tile, position) in chunk.get::<Tile>().unwrap().iter().zip(positions) {
+                tile.draw(world, context, *position, line_color);
+            }
         }
     }
 }
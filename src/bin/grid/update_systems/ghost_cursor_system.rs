@@ -18,7 +18,7 @@ impl UpdateSystem for GhostCursorSystem {
             let ghost = result.get_mut::<Ghost>().unwrap();
 
             if let Some(mouse_position) = mouse_position {
-                ghost.selected_tile.set_position(mouse_position);
+                ghost.position = mouse_position;
             }
         }
     }
@@ -1,5 +1,5 @@
 use crate::{
-    components::{ghost::Ghost, station::Station, tile::Tile},
+    components::{ghost::Ghost, line_color::LineColor, station::Station, tile::Tile, train::Train},
     schedulers::update_scheduler::UpdateSystem,
     Connections, Rail,
 };
@@ -11,6 +11,8 @@ use crate::{GridPosition, Transformer, World};
 #[derive(Default)]
 pub struct InputHandlerSystem {
     last_position: Option<GridPosition>,
+    /// The shared colour of the rail line being drawn.
+    line: Option<usize>,
 }
 
 impl UpdateSystem for InputHandlerSystem {
@@ -18,28 +20,27 @@ impl UpdateSystem for InputHandlerSystem {
         let mouse_position = context.get_mouse_position();
         let clicked_position = Transformer::position(world, mouse_position);
 
-        let mut ghost_query = world.query().with_component::<Ghost>().iter(world);
+        let key = context.get_key_pressed();
+        if key == Some(KeyboardKey::KEY_C) {
+            self.clear_grid(world);
+            self.reset_trains(world);
+        }
+
+        if key == Some(KeyboardKey::KEY_L) {
+            self.recolor_line(world);
+        }
 
-        let mut ghost = ghost_query.next().unwrap();
+        let mut ghost = world.query().with_component::<Ghost>().single(world);
         let ghost = ghost.get_mut::<Ghost>().unwrap();
 
-        match context.get_key_pressed() {
-            Some(KeyboardKey::KEY_C) => {
-                // self.clear_grid(world);
-                // self.reset_trains(world);
-            }
+        match key {
             Some(KeyboardKey::KEY_S) => {
                 ghost.selected_tile = Tile::Station(Station {
                     color: Color::RED.alpha(0.5),
                     ..Default::default()
                 })
             }
-            Some(KeyboardKey::KEY_R) => {
-                ghost.selected_tile = Tile::Rail(Rail {
-                    color: Color::BLACK.alpha(0.5),
-                    ..Default::default()
-                })
-            }
+            Some(KeyboardKey::KEY_R) => ghost.selected_tile = Tile::Rail(Rail::default()),
             _ => (),
         }
 
@@ -84,44 +85,32 @@ impl UpdateSystem for InputHandlerSystem {
 }
 
 impl InputHandlerSystem {
-    fn spawn_tile(&self, world: &mut World, clicked_position: GridPosition, tile: Tile) {
-        let query = world.query().with_component::<Tile>().iter(world);
-
+    fn spawn_tile(&mut self, world: &mut World, clicked_position: GridPosition, tile: Tile) {
         let mut new_connections = Connections::default();
-        for mut result in query {
-            let tile = result.get_mut::<Tile>().unwrap();
-            let tile_position = tile.get_position();
-
-            if self
-                .last_position
-                .is_some_and(|last_position| last_position == tile_position)
-                && self
-                    .last_position
-                    .is_some_and(|last_position| last_position.manhattan(&clicked_position) != 0)
-            {
-                tile.get_connections_mut().0.insert(clicked_position);
-                new_connections.0.insert(tile_position);
+
+        if let Some(last_position) = self
+            .last_position
+            .filter(|last_position| last_position.manhattan(&clicked_position) != 0)
+        {
+            let last_tile = world
+                .index::<GridPosition>()
+                .unwrap()
+                .get_single(&last_position);
+
+            if let Some(last_tile) = last_tile {
+                let last_tile = world.get_component_mut::<Tile>(last_tile).unwrap();
+                last_tile.get_connections_mut().0.insert(clicked_position);
+                new_connections.0.insert(last_position);
             }
         }
 
-        let mut query = world
-            .query()
-            .with_component::<Tile>()
-            .iter(world)
-            .collect::<Vec<_>>();
-
-        let overlap_index = query.iter().enumerate().find_map(|(index, result)| {
-            let tile = result.get::<Tile>().unwrap();
-            let tile_position = tile.get_position();
-
-            if tile_position == clicked_position {
-                return Some(index);
-            }
-            None
-        });
+        let overlap_tile = world
+            .index::<GridPosition>()
+            .unwrap()
+            .get_single(&clicked_position);
 
-        if let Some(index) = overlap_index {
-            let overlap_tile = query.get_mut(index).unwrap().get_mut::<Tile>().unwrap();
+        if let Some(overlap_tile) = overlap_tile {
+            let overlap_tile = world.get_component_mut::<Tile>(overlap_tile).unwrap();
             match tile {
                 Tile::Rail(_) => {
                     let tile_connections = overlap_tile.get_connections_mut();
@@ -132,30 +121,32 @@ impl InputHandlerSystem {
                         connections_offset: 0.25,
                         color: Color::RED,
                         connections: overlap_tile.get_connections_mut().clone(),
-                        position: clicked_position,
                         connections_color: Color::BLACK,
                     });
                 }
             }
         } else {
             match tile {
-                Tile::Rail(_) => world
-                    .create_entity()
-                    .with_component(Tile::Rail(Rail {
-                        color: Color::BLACK,
-                        connections: new_connections,
-                        position: clicked_position,
-                    }))
-                    .spawn(),
+                Tile::Rail(_) => {
+                    let line = self.line(world);
+                    world
+                        .create_entity()
+                        .with_component(Tile::Rail(Rail {
+                            connections: new_connections,
+                        }))
+                        .with_component(clicked_position)
+                        .with_shared::<LineColor>(line)
+                        .spawn()
+                }
                 Tile::Station(_) => world
                     .create_entity()
                     .with_component(Tile::Station(Station {
                         connections_offset: 0.25,
                         color: Color::RED,
                         connections: new_connections,
-                        position: clicked_position,
                         connections_color: Color::BLACK,
                     }))
+                    .with_component(clicked_position)
                     .spawn(),
             };
         }
@@ -193,17 +184,36 @@ impl InputHandlerSystem {
     //     }
     // }
 
-    // fn clear_grid(&self, world: &mut World) {
-    //     let (_, entity_ids) = world.query().with_component::<Tile>().run();
-    //     entity_ids
-    //         .iter()
-    //         .for_each(|entity_id| world.remove_entity(*entity_id));
-    // }
+    /// The cached line, or a new one if its colour entity has been despawned since.
+    fn line(&mut self, world: &mut World) -> usize {
+        match self
+            .line
+            .filter(|line| world.shared_value::<LineColor>(*line).is_some())
+        {
+            Some(line) => line,
+            None => *self.line.insert(world.add_shared(LineColor(Color::BLACK))),
+        }
+    }
 
-    // fn reset_trains(&self, world: &mut World) {
-    //     let (_, entity_ids) = world.query().with_component::<Train>().run();
-    //     entity_ids
-    //         .iter()
-    //         .for_each(|entity_id| world.remove_entity(*entity_id));
-    // }
+    /// Switches the colour of every rail on the line at once.
+    fn recolor_line(&mut self, world: &mut World) {
+        let line = self.line(world);
+        let Some(line_color) = world.shared_value_mut::<LineColor>(line) else {
+            return;
+        };
+
+        line_color.0 = if line_color.0 == Color::BLACK {
+            Color::DARKBLUE
+        } else {
+            Color::BLACK
+        };
+    }
+
+    fn clear_grid(&self, world: &mut World) {
+        world.query().with_component::<Tile>().despawn_all(world);
+    }
+
+    fn reset_trains(&self, world: &mut World) {
+        world.query().with_component::<Train>().despawn_all(world);
+    }
 }
@@ -15,15 +15,19 @@ pub struct TrainRouteSystem;
 
 impl UpdateSystem for TrainRouteSystem {
     fn update(&mut self, world: &mut World, _: &mut RaylibHandle) {
-        let train_query = world.query().with_component::<Train>().iter(world);
+        let train_query = world.query().with_component::<Train>().iter_sorted(world);
         let tile_results = world
             .query()
             .with_component::<Tile>()
-            .iter(world)
+            .with_component::<GridPosition>()
+            .iter_sorted(world)
             .collect::<Vec<_>>();
         let tiles = tile_results
             .iter()
-            .map(|result| result.get::<Tile>().unwrap())
+            .map(|result| {
+                let tile = result.get::<Tile>().unwrap();
+                (tile, *result.get::<GridPosition>().unwrap())
+            })
             .collect::<Vec<_>>();
 
         for mut result in train_query {
@@ -54,12 +58,16 @@ impl UpdateSystem for TrainRouteSystem {
 }
 
 impl TrainRouteSystem {
-    fn neighbor_tiles(&self, tiles: &[&Tile], position: GridPosition) -> Vec<GridPosition> {
+    fn neighbor_tiles(
+        &self,
+        tiles: &[(&Tile, GridPosition)],
+        position: GridPosition,
+    ) -> Vec<GridPosition> {
         tiles
             .iter()
-            .filter_map(|tile| {
+            .filter_map(|(tile, tile_position)| {
                 if tile.get_connections().0.contains(&position) {
-                    Some(tile.get_position())
+                    Some(*tile_position)
                 } else {
                     None
                 }
@@ -69,7 +77,7 @@ impl TrainRouteSystem {
 
     fn path_between(
         &self,
-        rails: &[&Tile],
+        rails: &[(&Tile, GridPosition)],
         from: GridPosition,
         to: GridPosition,
         direction: Direction,
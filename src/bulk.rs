@@ -0,0 +1,282 @@
+use crate::{column_for, ArchetypeId, ComponentId, EntityId, Lifecycle, QueryCreator, World};
+use std::{any::Any, collections::BTreeMap};
+
+impl QueryCreator {
+    /// Despawns every matching entity and returns how many there were. Archetypes whose
+    /// entities all match are cleared at once instead of row by row.
+    pub fn despawn_all(&self, world: &mut World) -> usize {
+        let entities = self.entities(world);
+
+        world.deferred(|world| {
+            // Detaching may move entities between archetypes, so it happens before grouping.
+            entities.iter().for_each(|entity| {
+                world.detach_hierarchy(*entity);
+                world.remove_pairs_targeting(*entity);
+            });
+
+            let (tables, rows) = world.whole_tables(&entities);
+            rows.into_iter()
+                .for_each(|entity| world.despawn_entity(entity, true));
+            tables
+                .into_iter()
+                .for_each(|archetype_id| world.despawn_table(archetype_id));
+        });
+
+        entities.len()
+    }
+
+    /// Gives every matching entity a clone of `component`, replacing any `T` it already has.
+    /// Returns how many entities there were. Archetypes whose entities all match are moved to
+    /// their new archetype at once.
+    pub fn insert_all<T: Any + Send + Sync + Clone>(
+        &self,
+        world: &mut World,
+        component: T,
+    ) -> usize {
+        let component_id = ComponentId::of::<T>();
+        let entities = self.entities(world);
+        world.register_type_name::<T>();
+
+        world.deferred(|world| {
+            let (tables, rows) = world.whole_tables(&entities);
+
+            let (moved, replaced): (Vec<_>, Vec<_>) =
+                tables.into_iter().partition(|archetype_id| {
+                    !world.sparse_sets.contains_key(&component_id)
+                        && !world.archetypes[*archetype_id]
+                            .column_index
+                            .contains_key(&component_id)
+                });
+
+            let replaced = replaced
+                .into_iter()
+                .flat_map(|archetype_id| world.archetypes[archetype_id].entities.clone())
+                .collect::<Vec<_>>();
+            replaced
+                .into_iter()
+                .chain(rows)
+                .for_each(|entity| world.insert_component(entity, component.clone()));
+
+            moved.into_iter().for_each(|from| {
+                let mut columns = world.empty_columns(from);
+                columns.insert(component_id, column_for::<T>());
+                let to = world.archetype_for(columns.keys().copied().collect(), columns);
+
+                let moved_entities = world.archetypes[from].entities.clone();
+                world.move_table(from, to, false);
+
+                let archetype = &mut world.archetypes[to];
+                let components =
+                    &mut archetype.columns[archetype.column_index[&component_id]].components;
+                components.reserve(moved_entities.len());
+                moved_entities
+                    .iter()
+                    .for_each(|_| components.push(component.clone()));
+
+                [Lifecycle::Add, Lifecycle::Insert]
+                    .into_iter()
+                    .for_each(|lifecycle| {
+                        moved_entities.iter().for_each(|entity| {
+                            world.trigger(*entity, component_id, lifecycle);
+                        });
+                    });
+            });
+        });
+
+        entities.len()
+    }
+
+    /// Removes and drops the `T` component of every matching entity and returns how many had
+    /// one. Archetypes whose entities all match are moved to their new archetype at once.
+    pub fn remove_all<T: Any>(&self, world: &mut World) -> usize {
+        let component_id = ComponentId::of::<T>();
+        let mut entities = self.entities(world);
+        entities.retain(|entity| world.has_component_id(*entity, component_id));
+
+        world.deferred(|world| {
+            let (tables, rows) = if world.sparse_sets.contains_key(&component_id) {
+                (vec![], entities.clone())
+            } else {
+                world.whole_tables(&entities)
+            };
+
+            rows.into_iter().for_each(|entity| {
+                world.remove_component::<T>(entity);
+            });
+
+            tables.into_iter().for_each(|from| {
+                world.archetypes[from]
+                    .entities
+                    .clone()
+                    .into_iter()
+                    .for_each(|entity| world.trigger(entity, component_id, Lifecycle::Remove));
+
+                let mut columns = world.empty_columns(from);
+                columns.remove(&component_id);
+                let to = world.archetype_for(columns.keys().copied().collect(), columns);
+
+                world.move_table(from, to, true);
+            });
+        });
+
+        entities.len()
+    }
+
+    fn entities(&self, world: &World) -> Vec<EntityId> {
+        self.iter(world).map(|result| result.entity()).collect()
+    }
+}
+
+impl World {
+    /// Splits `entities` into the archetypes they fill completely and the remaining entities.
+    fn whole_tables(&self, entities: &[EntityId]) -> (Vec<ArchetypeId>, Vec<EntityId>) {
+        let mut archetypes = BTreeMap::<ArchetypeId, Vec<EntityId>>::new();
+        entities.iter().for_each(|entity| {
+            let archetype_id = self.entity_index[entity].archetype_id;
+            archetypes.entry(archetype_id).or_default().push(*entity);
+        });
+
+        let mut tables = vec![];
+        let mut rows = vec![];
+        archetypes.into_iter().for_each(|(archetype_id, entities)| {
+            if entities.len() == self.archetypes[archetype_id].entities.len() {
+                tables.push(archetype_id);
+            } else {
+                rows.extend(entities);
+            }
+        });
+
+        (tables, rows)
+    }
+
+    /// Despawns every entity of the archetype, clearing its columns at once.
+    fn despawn_table(&mut self, archetype_id: ArchetypeId) {
+        let entities = self.archetypes[archetype_id].entities.clone();
+
+        entities.iter().for_each(|entity| {
+            self.component_ids(*entity)
+                .into_iter()
+                .for_each(|component_id| {
+                    self.trigger(*entity, component_id, Lifecycle::Remove);
+                });
+        });
+
+        entities.iter().for_each(|entity| {
+            self.sparse_sets.values_mut().for_each(|sparse_set| {
+                sparse_set.remove_and_drop(*entity);
+            });
+            self.entity_index.remove(entity);
+            self.observers.remove(entity);
+        });
+
+        let archetype = &mut self.archetypes[archetype_id];
+        archetype
+            .columns
+            .iter_mut()
+            .for_each(|column| column.components.clear());
+        archetype.entities.clear();
+
+        self.structural_changes += entities.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{StorageType, World};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, PartialEq, Clone)]
+    struct Train(u32);
+    #[derive(Debug, PartialEq, Clone)]
+    struct Stopped;
+    struct Wagon;
+    struct Tile;
+    struct Reserved;
+
+    #[test]
+    fn insert_all_and_remove_all_move_whole_tables() {
+        let mut world = World::default();
+        let trains = (0..6)
+            .map(|index| {
+                let train = world
+                    .create_entity()
+                    .with_component(Train(index))
+                    .spawn()
+                    .id;
+                if index < 2 {
+                    world.insert_component(train, Wagon);
+                }
+                train
+            })
+            .collect::<Vec<_>>();
+        let tile = world.create_entity().with_component(Tile).spawn().id;
+        world.insert_component(trains[5], Stopped);
+
+        let added = Arc::new(Mutex::new(vec![]));
+        let log = added.clone();
+        world.on_add::<Stopped>(move |_, entity, _| log.lock().unwrap().push(entity));
+
+        let query = world.query().with_component::<Train>();
+        assert_eq!(query.insert_all(&mut world, Stopped), 6);
+
+        let mut added = added.lock().unwrap().clone();
+        added.sort();
+        assert_eq!(added, trains[..5]);
+        assert!(trains
+            .iter()
+            .all(|train| world.has_component::<Stopped>(*train)));
+        assert_eq!(world.get_component::<Train>(trains[3]), Some(&Train(3)));
+        assert_eq!(world.get_component::<Train>(trains[1]), Some(&Train(1)));
+        assert!(world.has_component::<Wagon>(trains[0]));
+        assert!(!world.has_component::<Stopped>(tile));
+
+        let query = world.query().with_component::<Wagon>();
+        assert_eq!(query.remove_all::<Stopped>(&mut world), 2);
+        assert_eq!(query.remove_all::<Stopped>(&mut world), 0);
+        assert!(!world.has_component::<Stopped>(trains[0]));
+        assert!(world.has_component::<Stopped>(trains[2]));
+        assert_eq!(world.get_component::<Train>(trains[0]), Some(&Train(0)));
+    }
+
+    #[test]
+    fn despawn_all_clears_partial_and_whole_tables() {
+        let mut world = World::default();
+        world.set_storage_type::<Reserved>(StorageType::SparseSet);
+        let counter = Arc::new(());
+        let trains = (0..6)
+            .map(|index| {
+                let train = world
+                    .create_entity()
+                    .with_component(Train(index))
+                    .with_component(counter.clone())
+                    .spawn()
+                    .id;
+                if index < 2 {
+                    world.insert_component(train, Wagon);
+                }
+                train
+            })
+            .collect::<Vec<_>>();
+        world.insert_component(trains[4], Reserved);
+        world.set_parent(trains[3], trains[0]);
+
+        let kept = world.create_entity().with_component(Tile).spawn().id;
+        world.set_parent(kept, trains[1]);
+
+        let query = world.query().with_component::<Train>();
+        assert_eq!(query.despawn_all(&mut world), 6);
+
+        assert!(trains.iter().all(|train| !world.contains(*train)));
+        assert_eq!(Arc::strong_count(&counter), 1);
+        assert_eq!(world.parent(kept), None);
+        assert_eq!(query.iter(&world).count(), 0);
+        assert_eq!(
+            world
+                .query()
+                .with_component::<Reserved>()
+                .iter(&world)
+                .count(),
+            0
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{column_for, AnyVec, ComponentId, World};
+use std::any::Any;
+
+/// A set of component types, written as a tuple such as `(Tile, GridPosition)`.
+pub trait Bundle: 'static {
+    /// An empty column for every component of the bundle, registering their names in `world`.
+    fn columns(world: &mut World) -> Vec<(ComponentId, AnyVec)>;
+}
+
+macro_rules! impl_bundle {
+    ($($component:ident),+) => {
+        impl<$($component: Any + Send + Sync),+> Bundle for ($($component,)+) {
+            fn columns(world: &mut World) -> Vec<(ComponentId, AnyVec)> {
+                vec![$({
+                    world.register_type_name::<$component>();
+                    (ComponentId::of::<$component>(), column_for::<$component>())
+                }),+]
+            }
+        }
+    };
+}
+
+impl_bundle!(A);
+impl_bundle!(A, B);
+impl_bundle!(A, B, C);
+impl_bundle!(A, B, C, D);
+impl_bundle!(A, B, C, D, E);
+impl_bundle!(A, B, C, D, E, F);
+impl_bundle!(A, B, C, D, E, F, G);
+impl_bundle!(A, B, C, D, E, F, G, H);
+
+impl World {
+    /// Creates a world with room for `entities` entities.
+    pub fn with_capacity(entities: usize) -> Self {
+        let mut world = Self::default();
+        world.reserve_entities(entities);
+        world
+    }
+
+    /// Makes room for `additional` more entities in the entity index.
+    pub fn reserve_entities(&mut self, additional: usize) {
+        self.entity_index.reserve(additional);
+    }
+
+    /// Makes room for `additional` more entities with exactly the components of `B`.
+    pub fn reserve_for<B: Bundle>(&mut self, additional: usize) {
+        self.reserve_entities(additional);
+
+        let (sparse, columns): (Vec<_>, Vec<_>) = B::columns(self)
+            .into_iter()
+            .partition(|(component_id, _)| self.sparse_sets.contains_key(component_id));
+
+        sparse.into_iter().for_each(|(component_id, _)| {
+            self.sparse_sets
+                .get_mut(&component_id)
+                .unwrap()
+                .reserve(additional);
+        });
+
+        let components_set = columns
+            .iter()
+            .map(|(component_id, _)| *component_id)
+            .collect();
+        let archetype_id = self.archetype_for(components_set, columns.into_iter().collect());
+
+        self.reserved.insert(archetype_id);
+
+        let archetype = &mut self.archetypes[archetype_id];
+        archetype.entities.reserve(additional);
+        archetype
+            .columns
+            .iter_mut()
+            .for_each(|column| column.components.reserve(additional));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ComponentId, StorageType, World};
+
+    struct Tile(u32);
+    struct Rail;
+    struct Selected;
+
+    #[test]
+    fn reserve_for_allocates_every_column_once() {
+        let mut world = World::with_capacity(16);
+        world.set_storage_type::<Selected>(StorageType::SparseSet);
+        world.reserve_for::<(Tile, Rail, Selected)>(100);
+
+        let archetype_id = world.component_index[&ComponentId::of::<Tile>()]
+            .keys()
+            .copied()
+            .next()
+            .unwrap();
+        let tiles = &world.archetypes[archetype_id];
+        let tile_column = &tiles.columns[tiles.column_index[&ComponentId::of::<Tile>()]];
+        assert_eq!(tile_column.components.capacity(), 100);
+        assert_eq!(tiles.column_index.len(), 2);
+
+        (0..100).for_each(|index| {
+            let entity_record = world
+                .create_entity()
+                .with_component(Rail)
+                .with_component(Tile(index))
+                .with_component(Selected)
+                .spawn();
+            assert_eq!(entity_record.archetype_id, archetype_id);
+        });
+
+        let tiles = &world.archetypes[archetype_id];
+        let tile_column = &tiles.columns[tiles.column_index[&ComponentId::of::<Tile>()]];
+        assert_eq!(tile_column.components.capacity(), 100);
+        assert_eq!(world.get_component::<Tile>(99).unwrap().0, 99);
+
+        let stats = world.stats();
+        let selected = stats
+            .components
+            .iter()
+            .find(|component| component.component.ends_with("Selected"))
+            .unwrap();
+        assert_eq!(selected.len, 100);
+    }
+
+    #[test]
+    fn reserving_twice_keeps_the_existing_values() {
+        let mut world = World::default();
+
+        let first = world.create_entity().with_component(Tile(1)).spawn().id;
+        world.reserve_for::<(Tile,)>(10);
+        world.reserve_for::<(Tile,)>(3);
+
+        let archetype_id = world.entity_index[&first].archetype_id;
+        let tiles = &world.archetypes[archetype_id].columns[0].components;
+        assert_eq!(tiles.capacity(), 11);
+        assert_eq!(world.get_component::<Tile>(first).unwrap().0, 1);
+    }
+}
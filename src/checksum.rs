@@ -0,0 +1,289 @@
+use crate::{shared::SharedValue, Children, ComponentId, EcsError, EntityId, Parent, World};
+use std::{
+    any::{Any, TypeId},
+    hash::{Hash, Hasher},
+    slice,
+};
+
+/// Feeds the value at the given pointer to the hasher.
+pub(crate) type HashFn = unsafe fn(*const u8, &mut Fnv1a);
+
+unsafe fn hash_value<T: Hash>(component: *const u8, hasher: &mut Fnv1a) {
+    let component = unsafe { &*(component as *const T) };
+    component.hash(hasher);
+}
+
+/// 64-bit FNV-1a, which unlike the standard library's hasher is the same in every process.
+pub(crate) struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        bytes.iter().for_each(|byte| {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        });
+    }
+
+    // Integers are written as little-endian bytes, and `usize` as 64 bits, so that every
+    // platform gets the same result.
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl World {
+    /// Makes [`World::checksum`] hash `T` components, pairs of the relation `T` and shared `T`
+    /// values with their `Hash` impl.
+    pub fn register_hash<T: Any + Hash>(&mut self) {
+        self.hash_fns
+            .insert(TypeId::of::<T>(), hash_value::<T> as HashFn);
+        self.hash_fns.insert(
+            TypeId::of::<SharedValue<T>>(),
+            hash_value::<SharedValue<T>> as HashFn,
+        );
+    }
+
+    /// A hash of every entity and its components, to tell whether two worlds hold the same
+    /// state. Entities are visited by id and their components by name, so the result doesn't
+    /// depend on the order archetypes were created in.
+    ///
+    /// Components registered with [`World::register_hash`] are hashed with their `Hash` impl,
+    /// the hierarchy by the entities it links and dynamic components by their bytes. Zero-sized
+    /// components hold no state, so only their presence counts.
+    ///
+    /// # Panics
+    /// If an entity has any other Rust component, as changes to it would go unnoticed. See
+    /// [`World::try_checksum`].
+    pub fn checksum(&self) -> u64 {
+        self.try_checksum()
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Like [`World::checksum`], but fails on Rust components whose type isn't registered with
+    /// [`World::register_hash`].
+    pub fn try_checksum(&self) -> Result<u64, EcsError> {
+        let mut entities = self.entity_index.keys().copied().collect::<Vec<_>>();
+        entities.sort_unstable();
+
+        let mut hasher = Fnv1a::default();
+        entities.into_iter().try_for_each(|entity| {
+            hasher.write_usize(entity);
+
+            let mut components = self
+                .component_ids(entity)
+                .into_iter()
+                .map(|component_id| (self.component_name(component_id), component_id))
+                .collect::<Vec<_>>();
+            // Names can repeat, e.g. for dynamic components registered twice, so ids break ties.
+            components.sort_unstable();
+
+            components.into_iter().try_for_each(|(name, component_id)| {
+                hasher.write(name.as_bytes());
+                hasher.write_u8(0xff);
+                self.hash_component(entity, component_id, &mut hasher)
+            })
+        })?;
+
+        Ok(hasher.finish())
+    }
+
+    fn hash_component(
+        &self,
+        entity: EntityId,
+        component_id: ComponentId,
+        hasher: &mut Fnv1a,
+    ) -> Result<(), EcsError> {
+        let component = self.get_component_raw(entity, component_id).unwrap();
+
+        match component_id {
+            ComponentId::Type(type_id) | ComponentId::Pair(type_id, _) => {
+                let hash = self.hash_fns.get(&type_id).copied();
+
+                match hash.or_else(|| built_in_hash(type_id)) {
+                    Some(hash) => unsafe { hash(component, hasher) },
+                    None if self.column_of(entity, component_id).layout().size() == 0 => (),
+                    None => {
+                        let component = self.component_name(component_id);
+                        return Err(EcsError::UnhashedComponent(component));
+                    }
+                }
+            }
+            ComponentId::Dynamic(index) => {
+                let size = self.dynamic_components[index].layout().size();
+                hasher.write(unsafe { slice::from_raw_parts(component, size) });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The hash functions of the components the world manages itself.
+fn built_in_hash(type_id: TypeId) -> Option<HashFn> {
+    if type_id == TypeId::of::<Parent>() {
+        Some(hash_value::<Parent>)
+    } else if type_id == TypeId::of::<Children>() {
+        Some(hash_value::<Children>)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ComponentId, EcsError, World};
+    use std::alloc::Layout;
+
+    #[derive(Hash)]
+    struct GridPosition(u32, u32);
+    #[derive(Hash)]
+    struct Train(Vec<u32>);
+    #[derive(Hash)]
+    struct Selected(u32);
+    struct Derailed;
+    #[derive(Hash)]
+    struct ConnectedTo;
+
+    #[test]
+    fn checksums_ignore_archetype_order() {
+        let [plain, reserved] = [false, true].map(|reserve| {
+            let mut world = World::default();
+            world.register_hash::<GridPosition>();
+            world.register_hash::<Train>();
+            world.register_hash::<ConnectedTo>();
+            world.register_hash::<Selected>();
+            let fuel = world.register_dynamic_component("fuel", Layout::new::<u16>(), None);
+            if reserve {
+                world.reserve_for::<(Selected, GridPosition)>(4);
+                world.reserve_for::<(Train, GridPosition)>(4);
+            }
+
+            let station = world.create_entity().spawn().id;
+            let train = world
+                .create_entity()
+                .with_component(Train(vec![4, 2]))
+                .with_pair(ConnectedTo, station)
+                .spawn()
+                .id;
+            unsafe { world.insert_component_raw(train, fuel, &80u16 as *const u16 as *const u8) };
+            world.insert_component(train, GridPosition(2, 3));
+            world
+                .create_entity()
+                .with_component(Selected(0))
+                .with_component(GridPosition(1, 1))
+                .spawn();
+            world
+        });
+
+        assert_ne!(plain.archetype_index.len(), reserved.archetype_index.len());
+        assert_eq!(plain.checksum(), reserved.checksum());
+        assert_ne!(plain.checksum(), World::default().checksum());
+    }
+
+    #[test]
+    fn checksums_follow_hashed_values() {
+        let mut world = World::default();
+        world.register_hash::<GridPosition>();
+        world.register_hash::<Selected>();
+        let fuel = world.register_dynamic_component("fuel", Layout::new::<u16>(), None);
+
+        let station = world.create_entity().spawn().id;
+        let train = unsafe {
+            world
+                .create_entity()
+                .with_component_raw(fuel, &80u16 as *const u16 as *const u8)
+                .spawn()
+                .id
+        };
+        let rail = world
+            .create_entity()
+            .with_component(Selected(0))
+            .with_component(GridPosition(1, 1))
+            .spawn()
+            .id;
+        let checksum = world.checksum();
+
+        world.get_component_mut::<Selected>(rail).unwrap().0 = 1;
+        let selected = world.checksum();
+        assert_ne!(selected, checksum);
+
+        world.get_component_mut::<GridPosition>(rail).unwrap().0 = 0;
+        let moved = world.checksum();
+        assert_ne!(moved, selected);
+
+        let component = world.get_component_mut_raw(train, fuel).unwrap();
+        unsafe { *(component as *mut u16) = 60 };
+        let refueled = world.checksum();
+        assert_ne!(refueled, moved);
+
+        world.despawn(station);
+        assert_ne!(world.checksum(), refueled);
+    }
+
+    #[test]
+    fn checksums_order_components_sharing_a_name() {
+        let [first, second] = [(); 2].map(|_| {
+            let mut world = World::default();
+            let fuel = world.register_dynamic_component("fuel", Layout::new::<u16>(), None);
+            let reserve = world.register_dynamic_component("fuel", Layout::new::<u16>(), None);
+
+            unsafe {
+                world
+                    .create_entity()
+                    .with_component_raw(fuel, &80u16 as *const u16 as *const u8)
+                    .with_component_raw(reserve, &20u16 as *const u16 as *const u8)
+                    .spawn();
+            }
+            world
+        });
+
+        assert_eq!(first.checksum(), second.checksum());
+    }
+
+    #[test]
+    fn unhashed_components_fail_checksums() {
+        let mut world = World::default();
+        let station = world.create_entity().spawn().id;
+        let train = world.create_entity().spawn().id;
+        let parent = world.create_entity().with_component(Derailed).spawn().id;
+        world.set_parent(station, parent);
+        let checksum = world.checksum();
+
+        world.set_parent(station, train);
+        assert_ne!(world.checksum(), checksum);
+
+        world.insert_component(parent, String::from("express"));
+        assert_eq!(
+            world.try_checksum(),
+            Err(EcsError::UnhashedComponent(
+                world.component_name(ComponentId::of::<String>())
+            ))
+        );
+    }
+}
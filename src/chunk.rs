@@ -0,0 +1,251 @@
+use crate::{Archetype, ArchetypeId, ComponentId, EntityId, World};
+use std::{
+    any::{Any, TypeId},
+    slice, vec,
+};
+
+/// The rows of a single archetype matched by a query, with every table component available as
+/// one contiguous slice.
+pub struct Chunk<'w> {
+    world: &'w World,
+    archetype: &'w Archetype,
+}
+
+impl<'w> Chunk<'w> {
+    /// The entities stored in this chunk. Their indices match the component slices.
+    pub fn entities(&self) -> &'w [EntityId] {
+        &self.archetype.entities
+    }
+
+    pub fn len(&self) -> usize {
+        self.archetype.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.archetype.entities.is_empty()
+    }
+
+    pub fn get<T: Any>(&self) -> Option<&[T]> {
+        self.get_by_id(ComponentId::of::<T>())
+    }
+
+    pub fn get_mut<T: Any>(&mut self) -> Option<&mut [T]> {
+        self.get_mut_by_id(ComponentId::of::<T>())
+    }
+
+    /// A mutable `T` column alongside a `U` column, e.g. to add speeds to positions.
+    ///
+    /// # Panics
+    /// If `T` and `U` are the same type.
+    pub fn get_mut_with<T: Any, U: Any>(&mut self) -> Option<(&mut [T], &[U])> {
+        assert_ne!(
+            TypeId::of::<T>(),
+            TypeId::of::<U>(),
+            "a column can't be borrowed mutably and shared at once"
+        );
+
+        // `T` and `U` differ, so the columns don't overlap.
+        let others = self.get_by_id::<U>(ComponentId::of::<U>())?;
+        let others = unsafe { slice::from_raw_parts(others.as_ptr(), others.len()) };
+
+        Some((self.get_mut()?, others))
+    }
+
+    pub fn get_pair<R: Any>(&self, target: EntityId) -> Option<&[R]> {
+        self.get_by_id(ComponentId::pair::<R>(target))
+    }
+
+    pub fn get_pair_mut<R: Any>(&mut self, target: EntityId) -> Option<&mut [R]> {
+        self.get_mut_by_id(ComponentId::pair::<R>(target))
+    }
+
+    /// The shared `T` value every entity of this chunk references, see
+    /// [`World::add_shared`].
+    pub fn get_shared<T: Any>(&self) -> Option<&'w T> {
+        self.world.archetype_shared(self.archetype)
+    }
+
+    fn get_by_id<T: Any>(&self, component_id: ComponentId) -> Option<&[T]> {
+        let column_id = self.archetype.column_index.get(&component_id)?;
+        self.archetype.columns[*column_id].components.as_slice()
+    }
+
+    fn get_mut_by_id<T: Any>(&mut self, component_id: ComponentId) -> Option<&mut [T]> {
+        let components = self.get_by_id::<T>(component_id)?;
+        let components =
+            unsafe { slice::from_raw_parts_mut(components.as_ptr() as *mut T, components.len()) };
+
+        self.world.mark_all_changed(self.entities(), component_id);
+        Some(components)
+    }
+}
+
+pub struct ChunkIter<'w> {
+    world: &'w World,
+    archetype_ids: vec::IntoIter<ArchetypeId>,
+}
+
+impl<'w> ChunkIter<'w> {
+    pub(crate) fn new(world: &'w World, archetype_ids: Vec<ArchetypeId>) -> Self {
+        Self {
+            world,
+            archetype_ids: archetype_ids.into_iter(),
+        }
+    }
+}
+
+impl<'w> Iterator for ChunkIter<'w> {
+    type Item = Chunk<'w>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.archetype_ids
+            .by_ref()
+            .map(|archetype_id| &self.world.archetypes[archetype_id])
+            .find(|archetype| !archetype.entities.is_empty())
+            .map(|archetype| Chunk {
+                world: self.world,
+                archetype,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EcsError, StorageType, World};
+
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    struct Position(f32);
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    struct Speed(f32);
+    struct Wagon;
+    struct Reserved;
+
+    #[test]
+    fn chunks_expose_whole_columns() {
+        let mut world = World::default();
+
+        let entities = (0..4)
+            .map(|index| {
+                let entity = world
+                    .create_entity()
+                    .with_component(Position(index as f32))
+                    .spawn()
+                    .id;
+                world.insert_component(entity, Speed(1.0));
+                entity
+            })
+            .collect::<Vec<_>>();
+        world.insert_component(entities[3], Wagon);
+
+        let query = world
+            .query()
+            .with_component::<Position>()
+            .with_component::<Speed>();
+
+        let mut seen = vec![];
+        for mut chunk in query.iter_chunks(&world) {
+            let (positions, speeds) = chunk.get_mut_with::<Position, Speed>().unwrap();
+
+            assert_eq!(positions.len(), speeds.len());
+            positions
+                .iter_mut()
+                .zip(speeds)
+                .for_each(|(position, speed)| position.0 += speed.0);
+
+            seen.extend_from_slice(chunk.entities());
+        }
+
+        seen.sort();
+        assert_eq!(seen, entities);
+
+        entities.iter().enumerate().for_each(|(index, entity)| {
+            assert_eq!(
+                world.get_component::<Position>(*entity),
+                Some(&Position(index as f32 + 1.0))
+            );
+        });
+    }
+
+    #[test]
+    fn chunk_slices_check_the_layout() {
+        let mut world = World::default();
+        world.create_entity().with_component(Position(1.0)).spawn();
+
+        let mut chunks = world
+            .query()
+            .with_component::<Position>()
+            .iter_chunks(&world);
+        let mut chunk = chunks.next().unwrap();
+
+        assert_eq!(chunk.len(), 1);
+        assert_eq!(chunk.get::<Position>(), Some(&[Position(1.0)][..]));
+        assert!(chunk.get::<Speed>().is_none());
+        assert!(chunk.get_mut_with::<Position, Speed>().is_none());
+        assert!(chunks.next().is_none());
+    }
+
+    #[test]
+    fn empty_archetypes_are_skipped() {
+        let mut world = World::default();
+
+        let entity = world
+            .create_entity()
+            .with_component(Position(1.0))
+            .spawn()
+            .id;
+        world.insert_component(entity, Speed(1.0));
+
+        let chunks = world
+            .query()
+            .with_component::<Position>()
+            .iter_chunks(&world)
+            .map(|chunk| chunk.len())
+            .collect::<Vec<_>>();
+
+        assert_eq!(chunks, vec![1]);
+    }
+
+    #[test]
+    fn chunks_refuse_sparse_components() {
+        let mut world = World::default();
+        world.set_storage_type::<Reserved>(StorageType::SparseSet);
+
+        let reserved = world
+            .create_entity()
+            .with_component(Position(1.0))
+            .with_component(Reserved)
+            .spawn()
+            .id;
+        world.create_entity().with_component(Position(2.0)).spawn();
+
+        let query = world
+            .query()
+            .with_component::<Position>()
+            .with_component::<Reserved>();
+        assert_eq!(
+            query
+                .iter(&world)
+                .map(|result| result.entity())
+                .collect::<Vec<_>>(),
+            vec![reserved]
+        );
+        assert!(matches!(
+            query.try_iter_chunks(&world),
+            Err(EcsError::SparseChunkComponent(_))
+        ));
+        assert!(world
+            .query()
+            .with_component::<Reserved>()
+            .try_iter_chunks(&world)
+            .is_err());
+        assert_eq!(
+            world
+                .query()
+                .with_component::<Position>()
+                .iter_chunks(&world)
+                .map(|chunk| chunk.len())
+                .sum::<usize>(),
+            2
+        );
+    }
+}
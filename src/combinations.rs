@@ -0,0 +1,263 @@
+use crate::{ArchetypeId, QueryCreator, QueryIter, QueryResult, World};
+use std::array;
+
+/// A position among the matches. In storage order it's the index into `archetype_ids` and the
+/// row. When the query has a fixed order, it's the index into that order and `0`.
+type Cursor = (usize, usize);
+
+/// Every set of `K` distinct entities matching a query, each yielded once, in iteration order.
+pub struct Combinations<'w, const K: usize> {
+    results: QueryIter<'w>,
+    /// The `(archetype, row)` position of every match, if the query has a fixed order.
+    order: Option<Vec<(ArchetypeId, usize)>>,
+    cursors: Option<[Cursor; K]>,
+}
+
+impl<'w, const K: usize> Combinations<'w, K> {
+    fn new(results: QueryIter<'w>) -> Self {
+        let order = results
+            .order
+            .as_ref()
+            .map(|order| order.as_slice().to_vec());
+
+        let mut combinations = Self {
+            results,
+            order,
+            cursors: None,
+        };
+
+        if K > 0 {
+            let first = combinations.first_from((0, 0));
+            combinations.cursors = first.and_then(|first| combinations.fill([first; K], 1));
+        }
+
+        combinations
+    }
+
+    /// The first matching entity at or after `cursor`.
+    fn first_from(&self, (mut archetype_index, mut row): Cursor) -> Option<Cursor> {
+        if let Some(order) = &self.order {
+            return (archetype_index < order.len()).then_some((archetype_index, 0));
+        }
+
+        let world = self.results.world;
+
+        while let Some(archetype_id) = self.results.archetype_ids.get(archetype_index) {
+            let archetype = &world.archetypes[*archetype_id];
+
+            while row < archetype.entities.len() {
+                if self.results.has_sparse_components(archetype, row) {
+                    return Some((archetype_index, row));
+                }
+                row += 1;
+            }
+
+            archetype_index += 1;
+            row = 0;
+        }
+
+        None
+    }
+
+    fn after(&self, (archetype_index, row): Cursor) -> Option<Cursor> {
+        match self.order {
+            Some(_) => self.first_from((archetype_index + 1, 0)),
+            None => self.first_from((archetype_index, row + 1)),
+        }
+    }
+
+    /// Places the cursors from `start` on at the entities right after the previous cursor.
+    fn fill(&self, mut cursors: [Cursor; K], start: usize) -> Option<[Cursor; K]> {
+        for index in start..K {
+            cursors[index] = self.after(cursors[index - 1])?;
+        }
+
+        Some(cursors)
+    }
+
+    /// The combination following `cursors`, advancing the last cursor that still has room.
+    fn successor(&self, cursors: [Cursor; K]) -> Option<[Cursor; K]> {
+        (0..K).rev().find_map(|index| {
+            let mut next = cursors;
+            next[index] = self.after(cursors[index])?;
+            self.fill(next, index + 1)
+        })
+    }
+
+    fn fetch(&self, (archetype_index, row): Cursor) -> QueryResult<'w> {
+        let (archetype_id, row) = match &self.order {
+            Some(order) => order[archetype_index],
+            None => (self.results.archetype_ids[archetype_index], row),
+        };
+        let archetype = &self.results.world.archetypes[archetype_id];
+
+        self.results.fetch(archetype, row).unwrap()
+    }
+}
+
+impl<'w, const K: usize> Iterator for Combinations<'w, K> {
+    type Item = [QueryResult<'w>; K];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let cursors = self.cursors?;
+        self.cursors = self.successor(cursors);
+
+        Some(array::from_fn(|index| self.fetch(cursors[index])))
+    }
+}
+
+impl QueryCreator {
+    /// Iterates over every unordered set of `K` distinct matching entities, e.g. every pair of
+    /// trains for `K = 2`. Yields nothing for `K = 0`.
+    ///
+    /// Entities come in the query's iteration order. With [`QueryCreator::in_entity_order`] the
+    /// positions of every match are collected and sorted first, otherwise matches are walked in
+    /// place.
+    pub fn iter_combinations<'w, const K: usize>(&self, world: &'w World) -> Combinations<'w, K> {
+        Combinations::new(self.iter(world))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{StorageType, World};
+
+    #[derive(Debug, PartialEq)]
+    struct Train(u32);
+    struct Wagon;
+    struct Derailed;
+
+    #[test]
+    fn pairs_cover_every_unordered_pair_once() {
+        let mut world = World::default();
+        (0..5).for_each(|index| {
+            let train = world
+                .create_entity()
+                .with_component(Train(index))
+                .spawn()
+                .id;
+
+            if index % 2 == 0 {
+                world.insert_component(train, Wagon);
+            }
+        });
+
+        let mut pairs = world
+            .query()
+            .with_component::<Train>()
+            .iter_combinations::<2>(&world)
+            .map(|[a, b]| {
+                let (a, b) = (a.get::<Train>().unwrap().0, b.get::<Train>().unwrap().0);
+                assert_ne!(a, b);
+                (a.min(b), a.max(b))
+            })
+            .collect::<Vec<_>>();
+        pairs.sort();
+
+        let expected = (0..5)
+            .flat_map(|a| (a + 1..5).map(move |b| (a, b)))
+            .collect::<Vec<_>>();
+        assert_eq!(pairs, expected);
+    }
+
+    #[test]
+    fn combinations_skip_rows_without_sparse_components() {
+        let mut world = World::default();
+        world.set_storage_type::<Derailed>(StorageType::SparseSet);
+        (0..5).for_each(|index| {
+            let train = world
+                .create_entity()
+                .with_component(Train(index))
+                .spawn()
+                .id;
+
+            if index % 2 == 0 {
+                world.insert_component(train, Wagon);
+            }
+        });
+
+        [0, 3, 4].into_iter().for_each(|train| {
+            world.insert_component(train, Derailed);
+        });
+
+        let query = world
+            .query()
+            .with_component::<Train>()
+            .with_component::<Derailed>();
+
+        assert_eq!(query.iter_combinations::<2>(&world).count(), 3);
+        assert_eq!(query.iter_combinations::<3>(&world).count(), 1);
+        assert_eq!(query.iter_combinations::<4>(&world).count(), 0);
+        assert_eq!(query.iter_combinations::<0>(&world).count(), 0);
+    }
+
+    #[test]
+    fn combinations_can_mutate_both_entities() {
+        let mut world = World::default();
+        (0..5).for_each(|index| {
+            let train = world
+                .create_entity()
+                .with_component(Train(index))
+                .spawn()
+                .id;
+
+            if index % 2 == 0 {
+                world.insert_component(train, Wagon);
+            }
+        });
+
+        world
+            .query()
+            .with_component::<Train>()
+            .with_component::<Wagon>()
+            .iter_combinations::<2>(&world)
+            .for_each(|[mut a, mut b]| {
+                a.get_mut::<Train>().unwrap().0 += 10;
+                b.get_mut::<Train>().unwrap().0 += 10;
+            });
+
+        let mut trains = world
+            .query()
+            .with_component::<Train>()
+            .iter(&world)
+            .map(|result| result.get::<Train>().unwrap().0)
+            .collect::<Vec<_>>();
+        trains.sort();
+        assert_eq!(trains, vec![1, 3, 20, 22, 24]);
+    }
+
+    #[test]
+    fn combinations_follow_entity_order() {
+        let mut world = World::default();
+        (0..5).for_each(|index| {
+            let train = world
+                .create_entity()
+                .with_component(Train(index))
+                .spawn()
+                .id;
+
+            if index % 2 == 0 {
+                world.insert_component(train, Wagon);
+            }
+        });
+        // Moves train 1 behind the others in storage order.
+        world.insert_component(1, Wagon);
+
+        let entities = |[a, b]: [crate::QueryResult; 2]| (a.entity(), b.entity());
+        let query = world.query().with_component::<Train>();
+        assert_ne!(
+            query.iter_combinations::<2>(&world).next().map(entities),
+            Some((0, 1))
+        );
+
+        let pairs = query
+            .in_entity_order()
+            .iter_combinations::<2>(&world)
+            .map(entities)
+            .collect::<Vec<_>>();
+        let expected = (0..5)
+            .flat_map(|a| (a + 1..5).map(move |b| (a, b)))
+            .collect::<Vec<_>>();
+        assert_eq!(pairs, expected);
+    }
+}
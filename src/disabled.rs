@@ -0,0 +1,94 @@
+use crate::{EntityId, World};
+
+/// Marks an entity as disabled. Queries skip disabled entities unless they opt in with
+/// [`QueryCreator::with_disabled`](crate::QueryCreator::with_disabled), but their components
+/// stay untouched and can still be read through the world.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Disabled;
+
+impl World {
+    /// Hides `entity` from queries without despawning it. Does nothing if the entity doesn't
+    /// exist or is already disabled.
+    pub fn disable(&mut self, entity: EntityId) {
+        if self.is_disabled(entity) {
+            return;
+        }
+
+        self.insert_component(entity, Disabled);
+    }
+
+    /// Makes a disabled `entity` visible to queries again.
+    pub fn enable(&mut self, entity: EntityId) {
+        self.remove_component::<Disabled>(entity);
+    }
+
+    pub fn is_disabled(&self, entity: EntityId) -> bool {
+        self.has_component::<Disabled>(entity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{QueryCreator, StorageType};
+
+    #[derive(Debug, PartialEq)]
+    struct Train(u32);
+    struct Reserved;
+
+    fn trains(world: &World, query: QueryCreator) -> Vec<u32> {
+        let mut trains = query
+            .iter(world)
+            .map(|result| result.get::<Train>().unwrap().0)
+            .collect::<Vec<_>>();
+        trains.sort();
+        trains
+    }
+
+    #[test]
+    fn queries_skip_disabled_entities() {
+        let mut world = World::default();
+
+        let entities = (0..3)
+            .map(|index| {
+                world
+                    .create_entity()
+                    .with_component(Train(index))
+                    .spawn()
+                    .id
+            })
+            .collect::<Vec<_>>();
+
+        world.disable(entities[1]);
+        assert!(world.is_disabled(entities[1]));
+        assert_eq!(world.get_component::<Train>(entities[1]), Some(&Train(1)));
+
+        let query = |world: &World| world.query().with_component::<Train>();
+        assert_eq!(trains(&world, query(&world)), vec![0, 2]);
+        assert_eq!(trains(&world, query(&world).with_disabled()), vec![0, 1, 2]);
+        assert_eq!(
+            trains(&world, query(&world).with_component::<Disabled>()),
+            vec![1]
+        );
+        assert!(query(&world).get(&world, entities[1]).is_err());
+
+        world.enable(entities[1]);
+        assert!(!world.is_disabled(entities[1]));
+        assert_eq!(trains(&world, query(&world)), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn sparse_only_queries_skip_disabled_entities() {
+        let mut world = World::default();
+        world.set_storage_type::<Reserved>(StorageType::SparseSet);
+        assert!(!world.set_storage_type::<Disabled>(StorageType::SparseSet));
+
+        let train = world.create_entity().with_component(Train(0)).spawn().id;
+        world.insert_component(train, Reserved);
+        world.disable(train);
+
+        let query = |world: &World| world.query().with_component::<Reserved>();
+        assert_eq!(query(&world).iter(&world).count(), 0);
+        assert_eq!(query(&world).with_disabled().iter(&world).count(), 1);
+    }
+}
@@ -0,0 +1,285 @@
+use crate::{AnyVec, ComponentId, DropFn, EntityCreator, EntityId, Lifecycle, QueryCreator, World};
+use std::alloc::Layout;
+
+/// Describes a component registered at runtime, which has no Rust type.
+#[derive(Clone, Debug)]
+pub struct ComponentInfo {
+    name: String,
+    layout: Layout,
+    drop: Option<DropFn>,
+}
+
+impl ComponentInfo {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    pub fn drop_fn(&self) -> Option<DropFn> {
+        self.drop
+    }
+
+    fn column(&self) -> AnyVec {
+        AnyVec::with_drop(self.layout, self.drop)
+    }
+}
+
+impl World {
+    /// Registers a component that only exists at runtime. Its values are `layout` sized blobs,
+    /// dropped with `drop` when removed, if given.
+    ///
+    /// Dynamic components always live in the archetype tables, next to the Rust components of
+    /// the same entity.
+    pub fn register_dynamic_component(
+        &mut self,
+        name: impl Into<String>,
+        layout: Layout,
+        drop: Option<DropFn>,
+    ) -> ComponentId {
+        self.dynamic_components.push(ComponentInfo {
+            name: name.into(),
+            layout,
+            drop,
+        });
+
+        ComponentId::Dynamic(self.dynamic_components.len() - 1)
+    }
+
+    /// The description of a dynamic component, or `None` for any other kind of id.
+    pub fn component_info(&self, component_id: ComponentId) -> Option<&ComponentInfo> {
+        match component_id {
+            ComponentId::Dynamic(index) => self.dynamic_components.get(index),
+            _ => None,
+        }
+    }
+
+    /// Adds the dynamic component `component_id` to `entity`, replacing (and dropping) the
+    /// previous value. Does nothing if the entity doesn't exist.
+    ///
+    /// # Safety
+    /// `component` must point to a valid value of the component's layout, which is moved into
+    /// the world and must not be used or dropped by the caller afterwards. Like every component,
+    /// the value must be safe to send and share between threads.
+    ///
+    /// # Panics
+    /// If `component_id` isn't a registered dynamic component.
+    pub unsafe fn insert_component_raw(
+        &mut self,
+        entity: EntityId,
+        component_id: ComponentId,
+        component: *const u8,
+    ) {
+        let column = self.dynamic_column(component_id);
+
+        if !self.contains(entity) {
+            return;
+        }
+
+        unsafe { self.insert_component_with_column(entity, component_id, component, column) };
+    }
+
+    /// Points to the `component_id` value of `entity`, whatever kind of component it is.
+    pub fn get_component_raw(
+        &self,
+        entity: EntityId,
+        component_id: ComponentId,
+    ) -> Option<*const u8> {
+        if let Some(sparse_set) = self.sparse_sets.get(&component_id) {
+            return sparse_set.get_raw(entity);
+        }
+
+        let entity_record = self.entity_index.get(&entity)?;
+        let archetype = &self.archetypes[entity_record.archetype_id];
+        let column_id = archetype.column_index.get(&component_id)?;
+
+        archetype.columns[*column_id]
+            .components
+            .get_raw(entity_record.row)
+    }
+
+    pub fn get_component_mut_raw(
+        &mut self,
+        entity: EntityId,
+        component_id: ComponentId,
+    ) -> Option<*mut u8> {
+        self.mark_changed(entity, component_id);
+        self.get_component_raw(entity, component_id)
+            .map(|component| component as *mut u8)
+    }
+
+    /// Removes `component_id` from `entity` and drops it. Returns false if the entity doesn't
+    /// have the component.
+    pub fn drop_component(&mut self, entity: EntityId, component_id: ComponentId) -> bool {
+        if !self.has_component_id(entity, component_id) {
+            return false;
+        }
+
+        self.deferred(|world| {
+            world.trigger(entity, component_id, Lifecycle::Remove);
+            world.remove_column(entity, component_id, true)
+        })
+    }
+
+    fn dynamic_column(&self, component_id: ComponentId) -> AnyVec {
+        self.component_info(component_id)
+            .unwrap_or_else(|| panic!("{component_id:?} isn't a registered dynamic component"))
+            .column()
+    }
+}
+
+impl EntityCreator<'_> {
+    /// Adds a value of the dynamic component `component_id`.
+    ///
+    /// # Safety
+    /// `component` must point to a valid value of the component's layout, which is moved into
+    /// the entity and must not be used or dropped by the caller afterwards. Like every component,
+    /// the value must be safe to send and share between threads.
+    ///
+    /// # Panics
+    /// If `component_id` isn't a registered dynamic component.
+    pub unsafe fn with_component_raw(
+        mut self,
+        component_id: ComponentId,
+        component: *const u8,
+    ) -> Self {
+        let mut column = self.world.dynamic_column(component_id);
+        unsafe { column.push_raw(component) };
+
+        self.components.insert(component_id, column);
+        self
+    }
+}
+
+impl QueryCreator {
+    /// Matches entities with every component in `component_ids`, which may be of any kind.
+    pub fn with_component_ids(
+        mut self,
+        component_ids: impl IntoIterator<Item = ComponentId>,
+    ) -> Self {
+        self.component_ids.extend(component_ids);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drop_ptr;
+    use std::{mem::ManuallyDrop, sync::Arc};
+
+    #[derive(Debug, PartialEq)]
+    struct Tile(u32);
+
+    unsafe fn read<T: Copy>(component: *const u8) -> T {
+        unsafe { *(component as *const T) }
+    }
+
+    #[test]
+    fn dynamic_components_share_archetypes_with_rust_components() {
+        let mut world = World::default();
+        let fuel = world.register_dynamic_component("fuel", Layout::new::<f32>(), None);
+        let cargo = world.register_dynamic_component("cargo", Layout::new::<[u16; 3]>(), None);
+
+        assert_eq!(world.component_info(fuel).unwrap().name(), "fuel");
+        assert!(world.component_info(ComponentId::of::<Tile>()).is_none());
+
+        let entity = unsafe {
+            world
+                .create_entity()
+                .with_component(Tile(1))
+                .with_component_raw(fuel, &0.5f32 as *const f32 as *const u8)
+                .spawn()
+                .id
+        };
+        let other = world.create_entity().with_component(Tile(2)).spawn().id;
+        unsafe {
+            world.insert_component_raw(other, fuel, &1.5f32 as *const f32 as *const u8);
+            world.insert_component_raw(other, cargo, &[1u16, 2, 3] as *const [u16; 3] as *const u8);
+        }
+
+        assert_eq!(
+            world.entity_index[&entity].archetype_id,
+            world.archetype_index[&[ComponentId::of::<Tile>(), fuel].into()]
+        );
+        assert_eq!(world.get_component::<Tile>(other), Some(&Tile(2)));
+        unsafe {
+            assert_eq!(
+                read::<f32>(world.get_component_raw(entity, fuel).unwrap()),
+                0.5
+            );
+            assert_eq!(
+                read::<[u16; 3]>(world.get_component_raw(other, cargo).unwrap()),
+                [1, 2, 3]
+            );
+
+            *(world.get_component_mut_raw(other, fuel).unwrap() as *mut f32) = 2.5;
+        }
+
+        let mut fuels = world
+            .query()
+            .with_component::<Tile>()
+            .with_component_ids([fuel])
+            .iter(&world)
+            .map(|result| unsafe { read::<f32>(result.get_raw(fuel).unwrap()) })
+            .collect::<Vec<_>>();
+        fuels.sort_by(f32::total_cmp);
+        assert_eq!(fuels, vec![0.5, 2.5]);
+
+        assert!(world.drop_component(other, cargo));
+        assert!(!world.drop_component(other, cargo));
+        assert!(world.get_component_raw(other, cargo).is_none());
+    }
+
+    #[test]
+    fn dynamic_components_are_dropped() {
+        let mut world = World::default();
+        let shared = world.register_dynamic_component(
+            "shared",
+            Layout::new::<Arc<()>>(),
+            Some(drop_ptr::<Arc<()>>),
+        );
+        let counter = Arc::new(());
+
+        let spawn = |world: &mut World| {
+            let component = ManuallyDrop::new(counter.clone());
+            unsafe {
+                world
+                    .create_entity()
+                    .with_component_raw(shared, &*component as *const Arc<()> as *const u8)
+                    .spawn()
+                    .id
+            }
+        };
+
+        let first = spawn(&mut world);
+        let second = spawn(&mut world);
+        assert_eq!(Arc::strong_count(&counter), 3);
+
+        let replacement = ManuallyDrop::new(counter.clone());
+        unsafe {
+            world.insert_component_raw(first, shared, &*replacement as *const Arc<()> as *const u8)
+        };
+        assert_eq!(Arc::strong_count(&counter), 3);
+
+        world.despawn(first);
+        assert_eq!(Arc::strong_count(&counter), 2);
+
+        world.insert_component(second, Tile(0));
+        assert_eq!(Arc::strong_count(&counter), 2);
+
+        drop(world);
+        assert_eq!(Arc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "isn't a registered dynamic component")]
+    fn raw_components_need_a_registration() {
+        let mut world = World::default();
+        let entity = world.create_entity().spawn().id;
+
+        unsafe { world.insert_component_raw(entity, ComponentId::Dynamic(0), &0u8) };
+    }
+}
@@ -0,0 +1,188 @@
+use crate::{EntityId, EntityRecord, Res, ResMut, World};
+use std::{
+    any::{type_name, Any},
+    error::Error,
+    fmt,
+};
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum EcsError {
+    /// The entity was never spawned in this world.
+    NoSuchEntity(EntityId),
+    /// The entity existed, but has been despawned.
+    StaleEntity(EntityId),
+    MissingComponent {
+        entity: EntityId,
+        component: String,
+    },
+    MissingResource(String),
+    /// The value is already borrowed in a way that conflicts with the requested access.
+    BorrowConflict(String),
+    /// The same component was added twice to an entity being spawned.
+    DuplicateComponent(String),
+    /// A query expected exactly one entity, but none matched.
+    NoMatchingEntity,
+    /// A query expected exactly one entity, but several matched.
+    MultipleMatchingEntities,
+    /// The entity exists, but doesn't have every component the query asks for.
+    QueryMismatch(EntityId),
+    /// A chunk query asked for a sparse-set component, which archetype tables don't store.
+    SparseChunkComponent(String),
+    /// A checksum met a Rust component whose type isn't registered with
+    /// [`World::register_hash`].
+    UnhashedComponent(String),
+    /// There wasn't enough memory to make room for more components or entities.
+    AllocationFailed,
+}
+
+impl fmt::Display for EcsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSuchEntity(entity) => write!(f, "entity {entity} doesn't exist"),
+            Self::StaleEntity(entity) => write!(f, "entity {entity} has been despawned"),
+            Self::MissingComponent { entity, component } => {
+                write!(f, "entity {entity} has no {component} component")
+            }
+            Self::MissingResource(resource) => write!(f, "there is no {resource} resource"),
+            Self::BorrowConflict(name) => write!(f, "{name} is already borrowed"),
+            Self::DuplicateComponent(component) => {
+                write!(f, "the {component} component was added twice")
+            }
+            Self::NoMatchingEntity => write!(f, "no entity matches the query"),
+            Self::MultipleMatchingEntities => write!(f, "more than one entity matches the query"),
+            Self::QueryMismatch(entity) => write!(f, "entity {entity} doesn't match the query"),
+            Self::SparseChunkComponent(component) => {
+                write!(f, "chunks can't hold the sparse-set component {component}")
+            }
+            Self::UnhashedComponent(component) => {
+                write!(f, "the {component} component has no registered hash")
+            }
+            Self::AllocationFailed => write!(f, "memory allocation failed"),
+        }
+    }
+}
+
+impl Error for EcsError {}
+
+impl EcsError {
+    fn missing_component<T: Any>(entity: EntityId) -> Self {
+        Self::MissingComponent {
+            entity,
+            component: type_name::<T>().to_string(),
+        }
+    }
+
+    pub(crate) fn missing_resource<T: Any>() -> Self {
+        Self::MissingResource(type_name::<T>().to_string())
+    }
+}
+
+impl World {
+    /// The record of a live entity, telling apart ids that were never handed out from despawned
+    /// ones.
+    pub fn try_entity(&self, entity: EntityId) -> Result<EntityRecord, EcsError> {
+        match self.entity_index.get(&entity) {
+            Some(entity_record) => Ok(*entity_record),
+            None if entity < self.next_entity_id => Err(EcsError::StaleEntity(entity)),
+            None => Err(EcsError::NoSuchEntity(entity)),
+        }
+    }
+
+    pub fn try_get_component<T: Any>(&self, entity: EntityId) -> Result<&T, EcsError> {
+        self.try_entity(entity)?;
+        self.get_component(entity)
+            .ok_or_else(|| EcsError::missing_component::<T>(entity))
+    }
+
+    pub fn try_get_component_mut<T: Any>(&mut self, entity: EntityId) -> Result<&mut T, EcsError> {
+        self.try_entity(entity)?;
+        self.get_component_mut(entity)
+            .ok_or_else(|| EcsError::missing_component::<T>(entity))
+    }
+
+    pub fn try_insert_component<T: Any + Send + Sync>(
+        &mut self,
+        entity: EntityId,
+        component: T,
+    ) -> Result<(), EcsError> {
+        self.try_entity(entity)?;
+        self.insert_component(entity, component);
+        Ok(())
+    }
+
+    pub fn try_remove_component<T: Any>(&mut self, entity: EntityId) -> Result<T, EcsError> {
+        self.try_entity(entity)?;
+        self.remove_component(entity)
+            .ok_or_else(|| EcsError::missing_component::<T>(entity))
+    }
+
+    pub fn try_despawn(&mut self, entity: EntityId) -> Result<(), EcsError> {
+        self.try_entity(entity)?;
+        self.despawn(entity);
+        Ok(())
+    }
+
+    pub fn try_get_resource<T: Any>(&self) -> Result<Res<'_, T>, EcsError> {
+        self.resources.try_borrow()
+    }
+
+    pub fn try_get_resource_mut<T: Any>(&mut self) -> Result<&mut T, EcsError> {
+        self.get_resource_mut()
+            .ok_or_else(EcsError::missing_resource::<T>)
+    }
+
+    pub fn try_borrow_resource_mut<T: Any>(&self) -> Result<ResMut<'_, T>, EcsError> {
+        self.resources.try_borrow_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Tile(u32);
+    struct TileSize(u32);
+
+    #[test]
+    fn failures_are_reported_as_errors() {
+        let mut world = World::default();
+
+        let entity = world.create_entity().with_component(Tile(1)).spawn().id;
+        let despawned = world.create_entity().with_component(Tile(2)).spawn().id;
+        world.despawn(despawned);
+
+        assert_eq!(world.try_get_component::<Tile>(entity), Ok(&Tile(1)));
+        assert_eq!(
+            world.try_get_component::<Tile>(despawned),
+            Err(EcsError::StaleEntity(despawned))
+        );
+        assert_eq!(
+            world.try_insert_component(100, Tile(3)),
+            Err(EcsError::NoSuchEntity(100))
+        );
+        assert_eq!(
+            world.try_despawn(despawned),
+            Err(EcsError::StaleEntity(despawned))
+        );
+
+        assert_eq!(world.try_remove_component::<Tile>(entity), Ok(Tile(1)));
+        let error = world.try_get_component_mut::<Tile>(entity).unwrap_err();
+        assert_eq!(error, EcsError::missing_component::<Tile>(entity));
+        assert!(error.to_string().ends_with("Tile component"));
+    }
+
+    #[test]
+    fn missing_resources_are_reported_as_errors() {
+        let mut world = World::default();
+
+        assert!(matches!(
+            world.try_get_resource::<TileSize>(),
+            Err(EcsError::MissingResource(_))
+        ));
+
+        world.add_resource(TileSize(16));
+        world.try_get_resource_mut::<TileSize>().unwrap().0 = 32;
+        assert_eq!(world.try_get_resource::<TileSize>().unwrap().0, 32);
+    }
+}
@@ -0,0 +1,286 @@
+use crate::{EntityId, World};
+
+/// Points to the parent of an entity. Managed by [`World::set_parent`] and
+/// [`World::remove_parent`].
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Parent(EntityId);
+
+impl Parent {
+    pub fn get(&self) -> EntityId {
+        self.0
+    }
+}
+
+/// The children of an entity, in the order they were attached. Kept in sync with [`Parent`].
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct Children(Vec<EntityId>);
+
+impl Children {
+    pub fn as_slice(&self) -> &[EntityId] {
+        &self.0
+    }
+}
+
+impl World {
+    /// Attaches `child` to `parent`, detaching it from its previous parent first.
+    ///
+    /// Returns `false` without changing anything if either entity doesn't exist or if the
+    /// change would create a cycle.
+    pub fn set_parent(&mut self, child: EntityId, parent: EntityId) -> bool {
+        if !self.contains(child) || !self.contains(parent) {
+            return false;
+        }
+
+        if parent == child
+            || self
+                .iter_ancestors(parent)
+                .any(|ancestor| ancestor == child)
+        {
+            return false;
+        }
+
+        self.deferred(|world| {
+            world.remove_parent(child);
+            world.insert_component(child, Parent(parent));
+
+            match world.get_component_mut::<Children>(parent) {
+                Some(children) => children.0.push(child),
+                None => world.insert_component(parent, Children(vec![child])),
+            }
+        });
+
+        true
+    }
+
+    /// Detaches `child` from its parent and returns the former parent.
+    pub fn remove_parent(&mut self, child: EntityId) -> Option<EntityId> {
+        self.deferred(|world| {
+            let Parent(parent) = world.remove_component::<Parent>(child)?;
+
+            if let Some(children) = world.get_component_mut::<Children>(parent) {
+                children.0.retain(|sibling| *sibling != child);
+
+                if children.0.is_empty() {
+                    world.remove_component::<Children>(parent);
+                }
+            }
+
+            Some(parent)
+        })
+    }
+
+    pub fn parent(&self, entity: EntityId) -> Option<EntityId> {
+        self.get_component::<Parent>(entity).map(Parent::get)
+    }
+
+    pub fn children(&self, entity: EntityId) -> &[EntityId] {
+        self.get_component::<Children>(entity)
+            .map_or(&[], Children::as_slice)
+    }
+
+    /// Iterates over every descendant of `entity` in depth-first order, parents before their
+    /// children. The entity itself is not included.
+    pub fn iter_descendants(&self, entity: EntityId) -> Descendants<'_> {
+        Descendants {
+            world: self,
+            stack: self.children(entity).iter().rev().copied().collect(),
+        }
+    }
+
+    /// Iterates from the parent of `entity` up to the root of its hierarchy.
+    pub fn iter_ancestors(&self, entity: EntityId) -> Ancestors<'_> {
+        Ancestors {
+            world: self,
+            next: self.parent(entity),
+        }
+    }
+
+    /// Despawns `entity` together with all of its descendants.
+    pub fn despawn_recursive(&mut self, entity: EntityId) -> bool {
+        let descendants = self.iter_descendants(entity).collect::<Vec<_>>();
+
+        self.deferred(|world| {
+            // Leaves first, so no entity is orphaned before it is despawned.
+            descendants.into_iter().rev().for_each(|descendant| {
+                world.despawn(descendant);
+            });
+
+            world.despawn(entity)
+        })
+    }
+
+    /// Removes `entity` from its parent's children and orphans its own children.
+    pub(crate) fn detach_hierarchy(&mut self, entity: EntityId) {
+        self.remove_parent(entity);
+
+        if let Some(Children(children)) = self.remove_component::<Children>(entity) {
+            children.into_iter().for_each(|child| {
+                self.remove_component::<Parent>(child);
+            });
+        }
+    }
+}
+
+pub struct Descendants<'w> {
+    world: &'w World,
+    stack: Vec<EntityId>,
+}
+
+impl Iterator for Descendants<'_> {
+    type Item = EntityId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entity = self.stack.pop()?;
+        self.stack
+            .extend(self.world.children(entity).iter().rev().copied());
+
+        Some(entity)
+    }
+}
+
+pub struct Ancestors<'w> {
+    world: &'w World,
+    next: Option<EntityId>,
+}
+
+impl Iterator for Ancestors<'_> {
+    type Item = EntityId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entity = self.next?;
+        self.next = self.world.parent(entity);
+
+        Some(entity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Train;
+    #[derive(Debug, PartialEq)]
+    struct Wagon(u32);
+
+    #[test]
+    fn set_parent_keeps_both_sides_in_sync() {
+        let mut world = World::default();
+
+        let train = world.create_entity().with_component(Train).spawn().id;
+        let first = world.create_entity().with_component(Wagon(1)).spawn().id;
+        let second = world.create_entity().with_component(Wagon(2)).spawn().id;
+
+        assert!(world.set_parent(first, train));
+        assert!(world.set_parent(second, train));
+
+        assert_eq!(world.children(train), &[first, second]);
+        assert_eq!(world.parent(first), Some(train));
+        assert_eq!(world.parent(second), Some(train));
+        assert_eq!(world.get_component::<Wagon>(second), Some(&Wagon(2)));
+
+        assert_eq!(world.remove_parent(first), Some(train));
+        assert_eq!(world.children(train), &[second]);
+        assert_eq!(world.parent(first), None);
+
+        assert_eq!(world.remove_parent(second), Some(train));
+        assert!(!world.has_component::<Children>(train));
+    }
+
+    #[test]
+    fn set_parent_rejects_cycles() {
+        let mut world = World::default();
+
+        let root = world.create_entity().with_component(Train).spawn().id;
+        let child = world.create_entity().with_component(Train).spawn().id;
+        let grandchild = world.create_entity().with_component(Train).spawn().id;
+
+        assert!(world.set_parent(child, root));
+        assert!(world.set_parent(grandchild, child));
+
+        assert!(!world.set_parent(root, grandchild));
+        assert!(!world.set_parent(root, root));
+        assert_eq!(world.parent(root), None);
+
+        assert_eq!(
+            world.iter_ancestors(grandchild).collect::<Vec<_>>(),
+            vec![child, root]
+        );
+    }
+
+    #[test]
+    fn reparenting_moves_the_child() {
+        let mut world = World::default();
+
+        let a = world.create_entity().with_component(Train).spawn().id;
+        let b = world.create_entity().with_component(Train).spawn().id;
+        let wagon = world.create_entity().with_component(Wagon(1)).spawn().id;
+
+        world.set_parent(wagon, a);
+        world.set_parent(wagon, b);
+
+        assert!(world.children(a).is_empty());
+        assert_eq!(world.children(b), &[wagon]);
+    }
+
+    #[test]
+    fn iter_descendants_is_depth_first() {
+        let mut world = World::default();
+
+        let entities = (0..5)
+            .map(|index| {
+                world
+                    .create_entity()
+                    .with_component(Wagon(index))
+                    .spawn()
+                    .id
+            })
+            .collect::<Vec<_>>();
+
+        world.set_parent(entities[1], entities[0]);
+        world.set_parent(entities[2], entities[1]);
+        world.set_parent(entities[3], entities[0]);
+        world.set_parent(entities[4], entities[2]);
+
+        let descendants = world.iter_descendants(entities[0]).collect::<Vec<_>>();
+        assert_eq!(
+            descendants,
+            vec![entities[1], entities[2], entities[4], entities[3]]
+        );
+    }
+
+    #[test]
+    fn despawn_orphans_children() {
+        let mut world = World::default();
+
+        let train = world.create_entity().with_component(Train).spawn().id;
+        let wagon = world.create_entity().with_component(Wagon(1)).spawn().id;
+        world.set_parent(wagon, train);
+
+        assert!(world.despawn(train));
+
+        assert!(!world.contains(train));
+        assert!(world.contains(wagon));
+        assert_eq!(world.parent(wagon), None);
+    }
+
+    #[test]
+    fn despawn_recursive_removes_the_whole_tree() {
+        let mut world = World::default();
+
+        let other = world.create_entity().with_component(Train).spawn().id;
+        let train = world.create_entity().with_component(Train).spawn().id;
+        let wagon = world.create_entity().with_component(Wagon(1)).spawn().id;
+        let cargo = world.create_entity().with_component(Wagon(2)).spawn().id;
+        world.set_parent(wagon, train);
+        world.set_parent(cargo, wagon);
+        world.set_parent(train, other);
+
+        assert!(world.despawn_recursive(train));
+
+        assert!(!world.contains(train));
+        assert!(!world.contains(wagon));
+        assert!(!world.contains(cargo));
+        assert!(world.children(other).is_empty());
+        assert_eq!(world.entity_index.len(), 1);
+    }
+}
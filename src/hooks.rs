@@ -0,0 +1,322 @@
+use crate::{ComponentId, EntityId, World};
+use std::{any::Any, collections::VecDeque};
+
+/// Callback run when a component is added to, inserted into or removed from an entity.
+///
+/// Hooks only get shared access to the world; structural changes have to be queued in the
+/// [`Commands`], which are applied once the operation that triggered the hook has finished.
+pub type Hook = Box<dyn Fn(&World, EntityId, &mut Commands) + Send + Sync>;
+
+type Command = Box<dyn FnOnce(&mut World) + Send>;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Lifecycle {
+    /// The entity didn't have the component before. Runs after the value is stored.
+    Add,
+    /// The component was stored, either added or replacing a previous value.
+    Insert,
+    /// The component is about to be removed, either on its own or by a despawn. Runs while the
+    /// value can still be read.
+    Remove,
+}
+
+#[derive(Default)]
+pub(crate) struct ComponentHooks {
+    on_add: Vec<Hook>,
+    on_insert: Vec<Hook>,
+    on_remove: Vec<Hook>,
+}
+
+impl ComponentHooks {
+    fn get(&self, lifecycle: Lifecycle) -> &[Hook] {
+        match lifecycle {
+            Lifecycle::Add => &self.on_add,
+            Lifecycle::Insert => &self.on_insert,
+            Lifecycle::Remove => &self.on_remove,
+        }
+    }
+
+    fn get_mut(&mut self, lifecycle: Lifecycle) -> &mut Vec<Hook> {
+        match lifecycle {
+            Lifecycle::Add => &mut self.on_add,
+            Lifecycle::Insert => &mut self.on_insert,
+            Lifecycle::Remove => &mut self.on_remove,
+        }
+    }
+}
+
+/// An observer registered on a single entity.
+pub(crate) struct Observer {
+    component_id: ComponentId,
+    lifecycle: Lifecycle,
+    callback: Hook,
+}
+
+/// World changes queued by hooks and observers.
+#[derive(Default)]
+pub struct Commands {
+    queue: VecDeque<Command>,
+}
+
+impl Commands {
+    pub fn add(&mut self, command: impl FnOnce(&mut World) + Send + 'static) {
+        self.queue.push_back(Box::new(command));
+    }
+
+    pub fn insert_component<T: Any + Send + Sync>(&mut self, entity: EntityId, component: T) {
+        self.add(move |world| world.insert_component(entity, component));
+    }
+
+    pub fn remove_component<T: Any>(&mut self, entity: EntityId) {
+        self.add(move |world| {
+            world.remove_component::<T>(entity);
+        });
+    }
+
+    pub fn despawn(&mut self, entity: EntityId) {
+        self.add(move |world| {
+            world.despawn(entity);
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+impl World {
+    pub fn on_add<T: Any>(
+        &mut self,
+        hook: impl Fn(&World, EntityId, &mut Commands) + Send + Sync + 'static,
+    ) {
+        self.add_hook(ComponentId::of::<T>(), Lifecycle::Add, Box::new(hook));
+    }
+
+    pub fn on_insert<T: Any>(
+        &mut self,
+        hook: impl Fn(&World, EntityId, &mut Commands) + Send + Sync + 'static,
+    ) {
+        self.add_hook(ComponentId::of::<T>(), Lifecycle::Insert, Box::new(hook));
+    }
+
+    pub fn on_remove<T: Any>(
+        &mut self,
+        hook: impl Fn(&World, EntityId, &mut Commands) + Send + Sync + 'static,
+    ) {
+        self.add_hook(ComponentId::of::<T>(), Lifecycle::Remove, Box::new(hook));
+    }
+
+    /// Runs `callback` whenever `lifecycle` happens to the `T` component of `entity`. The
+    /// observer is dropped together with the entity.
+    pub fn observe<T: Any>(
+        &mut self,
+        entity: EntityId,
+        lifecycle: Lifecycle,
+        callback: impl Fn(&World, EntityId, &mut Commands) + Send + Sync + 'static,
+    ) {
+        if !self.contains(entity) {
+            return;
+        }
+
+        self.observers.entry(entity).or_default().push(Observer {
+            component_id: ComponentId::of::<T>(),
+            lifecycle,
+            callback: Box::new(callback),
+        });
+    }
+
+    fn add_hook(&mut self, component_id: ComponentId, lifecycle: Lifecycle, hook: Hook) {
+        self.hooks
+            .entry(component_id)
+            .or_default()
+            .get_mut(lifecycle)
+            .push(hook);
+    }
+
+    /// Runs the hooks and observers of `component_id` for `entity`, queueing their commands.
+    pub(crate) fn trigger(
+        &mut self,
+        entity: EntityId,
+        component_id: ComponentId,
+        lifecycle: Lifecycle,
+    ) {
+        self.update_index(entity, component_id, lifecycle);
+
+        let mut commands = std::mem::take(&mut self.commands);
+
+        let world = &*self;
+        let hooks = world
+            .hooks
+            .get(&component_id)
+            .map_or(&[][..], |hooks| hooks.get(lifecycle));
+        hooks
+            .iter()
+            .for_each(|hook| hook(world, entity, &mut commands));
+
+        world
+            .observers
+            .get(&entity)
+            .into_iter()
+            .flatten()
+            .filter(|observer| {
+                observer.component_id == component_id && observer.lifecycle == lifecycle
+            })
+            .for_each(|observer| (observer.callback)(world, entity, &mut commands));
+
+        self.commands = commands;
+    }
+
+    /// Runs `operation`, applying the commands queued by hooks once the outermost operation has
+    /// finished, so hooks never observe a half-done structural change.
+    pub(crate) fn deferred<R>(&mut self, operation: impl FnOnce(&mut Self) -> R) -> R {
+        self.command_depth += 1;
+        let result = operation(self);
+        self.command_depth -= 1;
+
+        if self.command_depth == 0 {
+            while let Some(command) = self.commands.queue.pop_front() {
+                command(self);
+            }
+
+            self.maintain();
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StorageType;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, PartialEq)]
+    struct Tile(u32);
+    struct Highlighted;
+
+    type Log = Arc<Mutex<Vec<(Lifecycle, EntityId, Option<u32>)>>>;
+
+    fn log_hooks(world: &mut World) -> Log {
+        let log = Log::default();
+
+        let add = log.clone();
+        world.on_add::<Tile>(move |world, entity, _| {
+            let tile = world.get_component::<Tile>(entity).map(|tile| tile.0);
+            add.lock().unwrap().push((Lifecycle::Add, entity, tile));
+        });
+
+        let insert = log.clone();
+        world.on_insert::<Tile>(move |world, entity, _| {
+            let tile = world.get_component::<Tile>(entity).map(|tile| tile.0);
+            insert
+                .lock()
+                .unwrap()
+                .push((Lifecycle::Insert, entity, tile));
+        });
+
+        let remove = log.clone();
+        world.on_remove::<Tile>(move |world, entity, _| {
+            let tile = world.get_component::<Tile>(entity).map(|tile| tile.0);
+            remove
+                .lock()
+                .unwrap()
+                .push((Lifecycle::Remove, entity, tile));
+        });
+
+        log
+    }
+
+    #[test]
+    fn hooks_run_on_every_structural_change() {
+        let mut world = World::default();
+        let log = log_hooks(&mut world);
+
+        let first = world.create_entity().with_component(Tile(1)).spawn().id;
+        let second = world.create_entity().with_component(Highlighted).spawn().id;
+        world.insert_component(second, Tile(2));
+        world.insert_component(second, Tile(3));
+        world.remove_component::<Tile>(second);
+        world.despawn(first);
+        world.despawn(second);
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                (Lifecycle::Add, first, Some(1)),
+                (Lifecycle::Insert, first, Some(1)),
+                (Lifecycle::Add, second, Some(2)),
+                (Lifecycle::Insert, second, Some(2)),
+                (Lifecycle::Insert, second, Some(3)),
+                (Lifecycle::Remove, second, Some(3)),
+                (Lifecycle::Remove, first, Some(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn hooks_run_for_sparse_components() {
+        let mut world = World::default();
+        world.set_storage_type::<Tile>(StorageType::SparseSet);
+        let log = log_hooks(&mut world);
+
+        let entity = world.create_entity().with_component(Tile(1)).spawn().id;
+        world.despawn(entity);
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                (Lifecycle::Add, entity, Some(1)),
+                (Lifecycle::Insert, entity, Some(1)),
+                (Lifecycle::Remove, entity, Some(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn commands_are_applied_after_the_operation() {
+        let mut world = World::default();
+
+        world.on_add::<Tile>(|world, entity, commands| {
+            assert!(world.has_component::<Tile>(entity));
+            commands.insert_component(entity, Highlighted);
+        });
+        world.on_remove::<Highlighted>(|_, entity, commands| {
+            commands.remove_component::<Tile>(entity);
+        });
+
+        let entity = world.create_entity().with_component(Tile(1)).spawn().id;
+        assert!(world.has_component::<Highlighted>(entity));
+
+        world.remove_component::<Highlighted>(entity);
+        assert!(!world.has_component::<Tile>(entity));
+        assert!(world.contains(entity));
+    }
+
+    #[test]
+    fn observers_only_see_their_entity() {
+        let mut world = World::default();
+        let removed = Arc::new(Mutex::new(vec![]));
+
+        let first = world.create_entity().with_component(Tile(1)).spawn().id;
+        let second = world.create_entity().with_component(Tile(2)).spawn().id;
+
+        let log = removed.clone();
+        world.observe::<Tile>(first, Lifecycle::Remove, move |world, entity, _| {
+            let tile = world.get_component::<Tile>(entity).unwrap().0;
+            log.lock().unwrap().push(tile);
+        });
+
+        world.despawn(second);
+        world.remove_component::<Tile>(first);
+        world.insert_component(first, Tile(3));
+        world.despawn(first);
+
+        assert_eq!(*removed.lock().unwrap(), vec![1, 3]);
+        assert!(world.observers.is_empty());
+    }
+}
@@ -0,0 +1,266 @@
+use crate::{ComponentId, EntityId, Lifecycle, World};
+use std::{
+    any::Any,
+    collections::HashMap,
+    hash::Hash,
+    mem::{self, take},
+};
+
+/// Maps the values of a component to the entities holding them. Created with
+/// [`World::register_index`] and kept up to date by the world.
+#[derive(Debug)]
+pub struct ValueIndex<T> {
+    entities: HashMap<T, Vec<EntityId>>,
+    values: HashMap<EntityId, T>,
+}
+
+impl<T: Hash + Eq + Clone> ValueIndex<T> {
+    /// The entities whose component equals `value`, in the order they got it.
+    pub fn get(&self, value: &T) -> &[EntityId] {
+        self.entities.get(value).map_or(&[], Vec::as_slice)
+    }
+
+    /// The entity whose component equals `value`, if exactly one has it.
+    pub fn get_single(&self, value: &T) -> Option<EntityId> {
+        match self.get(value) {
+            [entity] => Some(*entity),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.entities.contains_key(value)
+    }
+
+    /// How many entities are indexed.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    fn insert(&mut self, entity: EntityId, value: T) {
+        self.remove(entity);
+
+        self.entities.entry(value.clone()).or_default().push(entity);
+        self.values.insert(entity, value);
+    }
+
+    fn remove(&mut self, entity: EntityId) {
+        let Some(value) = self.values.remove(&entity) else {
+            return;
+        };
+
+        let entities = self.entities.get_mut(&value).unwrap();
+        entities.retain(|indexed| *indexed != entity);
+
+        if entities.is_empty() {
+            self.entities.remove(&value);
+        }
+    }
+}
+
+/// Type-erased access to a [`ValueIndex`].
+pub(crate) trait AnyIndex: Send + Sync {
+    /// Re-reads the component of `entity`, dropping the entry if it's gone.
+    fn refresh(&mut self, world: &World, entity: EntityId);
+
+    fn remove(&mut self, entity: EntityId);
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Any + Send + Sync + Hash + Eq + Clone> AnyIndex for ValueIndex<T> {
+    fn refresh(&mut self, world: &World, entity: EntityId) {
+        match world.get_component::<T>(entity) {
+            Some(value) => self.insert(entity, value.clone()),
+            None => ValueIndex::remove(self, entity),
+        }
+    }
+
+    fn remove(&mut self, entity: EntityId) {
+        ValueIndex::remove(self, entity);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl World {
+    /// Starts indexing the values of `T` components, see [`World::index`].
+    pub fn register_index<T: Any + Send + Sync + Hash + Eq + Clone>(&mut self) {
+        let component_id = ComponentId::of::<T>();
+        if self.indexes.contains_key(&component_id) {
+            return;
+        }
+
+        let mut index = ValueIndex::<T> {
+            entities: HashMap::new(),
+            values: HashMap::new(),
+        };
+
+        let mut entities = self.entity_index.keys().copied().collect::<Vec<_>>();
+        entities.sort_unstable();
+        entities
+            .into_iter()
+            .for_each(|entity| AnyIndex::refresh(&mut index, self, entity));
+
+        self.indexes.insert(component_id, Box::new(index));
+    }
+
+    /// The index of `T` values, if registered with [`World::register_index`].
+    ///
+    /// Values changed through mutable borrows since the last call are indexed again first.
+    pub fn index<T: Any + Send + Sync + Hash + Eq + Clone>(&mut self) -> Option<&ValueIndex<T>> {
+        self.refresh_indexes();
+
+        self.indexes
+            .get(&ComponentId::of::<T>())?
+            .as_any()
+            .downcast_ref()
+    }
+
+    /// Remembers that the `component_id` value of `entity` may have been changed through a
+    /// mutable borrow, so its index entry is refreshed before the next lookup.
+    pub(crate) fn mark_changed(&self, entity: EntityId, component_id: ComponentId) {
+        self.mark_all_changed(&[entity], component_id);
+    }
+
+    /// Like [`World::mark_changed`] for many entities, checking for an index and locking once.
+    pub(crate) fn mark_all_changed(&self, entities: &[EntityId], component_id: ComponentId) {
+        if !self.indexes.contains_key(&component_id) {
+            return;
+        }
+
+        let mut changed = self.changed.lock().unwrap();
+        changed.extend(entities.iter().map(|entity| (component_id, *entity)));
+    }
+
+    /// Keeps the indexes in sync with a structural change of `component_id`.
+    pub(crate) fn update_index(
+        &mut self,
+        entity: EntityId,
+        component_id: ComponentId,
+        lifecycle: Lifecycle,
+    ) {
+        let Some(mut index) = self.indexes.remove(&component_id) else {
+            return;
+        };
+
+        match lifecycle {
+            Lifecycle::Add | Lifecycle::Insert => index.refresh(self, entity),
+            Lifecycle::Remove => index.remove(entity),
+        }
+
+        self.indexes.insert(component_id, index);
+    }
+
+    fn refresh_indexes(&mut self) {
+        let changed = take(&mut *self.changed.lock().unwrap());
+        let mut indexes = mem::take(&mut self.indexes);
+
+        changed.into_iter().for_each(|(component_id, entity)| {
+            indexes
+                .get_mut(&component_id)
+                .unwrap()
+                .refresh(self, entity);
+        });
+
+        self.indexes = indexes;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+    struct GridPosition(u32, u32);
+    #[derive(PartialEq, Eq, Hash, Clone)]
+    struct Rail;
+
+    #[test]
+    fn index_follows_structural_changes() {
+        let mut world = World::default();
+
+        let existing = world
+            .create_entity()
+            .with_component(GridPosition(0, 0))
+            .spawn()
+            .id;
+        world.register_index::<GridPosition>();
+
+        let rail = world
+            .create_entity()
+            .with_component(GridPosition(1, 0))
+            .with_component(Rail)
+            .spawn()
+            .id;
+        let moved = world.create_entity().with_component(Rail).spawn().id;
+        world.insert_component(moved, GridPosition(1, 0));
+
+        let index = world.index::<GridPosition>().unwrap();
+        assert_eq!(index.get_single(&GridPosition(0, 0)), Some(existing));
+        assert_eq!(index.get(&GridPosition(1, 0)), &[rail, moved]);
+        assert_eq!(index.get_single(&GridPosition(1, 0)), None);
+
+        world.insert_component(moved, GridPosition(2, 0));
+        world.despawn(rail);
+        world.remove_component::<GridPosition>(existing);
+
+        let index = world.index::<GridPosition>().unwrap();
+        assert_eq!(index.get_single(&GridPosition(2, 0)), Some(moved));
+        assert!(!index.contains(&GridPosition(1, 0)));
+        assert!(!index.contains(&GridPosition(0, 0)));
+        assert_eq!(index.len(), 1);
+    }
+
+    #[test]
+    fn index_follows_mutations() {
+        let mut world = World::default();
+        world.register_index::<GridPosition>();
+
+        let entities = (0..3)
+            .map(|index| {
+                world
+                    .create_entity()
+                    .with_component(GridPosition(index, 0))
+                    .spawn()
+                    .id
+            })
+            .collect::<Vec<_>>();
+
+        world
+            .get_component_mut::<GridPosition>(entities[0])
+            .unwrap()
+            .1 = 5;
+        world
+            .query()
+            .with_component::<GridPosition>()
+            .iter(&world)
+            .for_each(|mut result| {
+                if result.entity() == entities[1] {
+                    result.get_mut::<GridPosition>().unwrap().1 = 6;
+                }
+            });
+        world
+            .query()
+            .with_component::<GridPosition>()
+            .iter_chunks(&world)
+            .for_each(|mut chunk| {
+                let entity = chunk.entities().iter().position(|e| *e == entities[2]);
+                chunk.get_mut::<GridPosition>().unwrap()[entity.unwrap()].1 = 7;
+            });
+
+        let index = world.index::<GridPosition>().unwrap();
+        assert_eq!(index.get_single(&GridPosition(0, 5)), Some(entities[0]));
+        assert_eq!(index.get_single(&GridPosition(1, 6)), Some(entities[1]));
+        assert_eq!(index.get_single(&GridPosition(2, 7)), Some(entities[2]));
+        assert_eq!(index.len(), 3);
+
+        assert!(world.index::<Rail>().is_none());
+    }
+}
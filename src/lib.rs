@@ -1,12 +1,53 @@
 mod any_vec;
+mod bulk;
+mod bundle;
+mod checksum;
+mod chunk;
+mod combinations;
+mod disabled;
+mod dynamic;
+mod error;
+mod hierarchy;
+mod hooks;
+mod index;
+mod maintenance;
+mod order;
+mod prefab;
+mod relation;
 mod resources;
-
-pub use any_vec::AnyVec;
-use resources::Resources;
+mod shared;
+mod sparse_set;
+mod stats;
+mod transfer;
+
+use any_vec::drop_ptr;
+pub use any_vec::{AnyVec, DropFn};
+pub use bundle::Bundle;
+use checksum::HashFn;
+pub use chunk::{Chunk, ChunkIter};
+pub use combinations::Combinations;
+pub use disabled::Disabled;
+pub use dynamic::ComponentInfo;
+pub use error::EcsError;
+pub use hierarchy::{Ancestors, Children, Descendants, Parent};
+pub use hooks::{Commands, Hook, Lifecycle};
+use hooks::{ComponentHooks, Observer};
+use index::AnyIndex;
+pub use index::ValueIndex;
+use prefab::CloneFn;
+pub use prefab::Prefab;
+pub use resources::{NonSendResources, Res, ResMut, Resources, Tick};
+pub use sparse_set::{SparseSet, StorageType};
+pub use stats::{ArchetypeStats, ColumnStats, ComponentStats, WorldStats};
 use std::{
     alloc::Layout,
-    any::{Any, TypeId},
-    collections::{BTreeSet, HashMap},
+    any::{type_name, Any, TypeId},
+    array,
+    collections::{BTreeSet, HashMap, HashSet},
+    mem::{needs_drop, ManuallyDrop},
+    ptr::read,
+    sync::Mutex,
+    vec,
 };
 
 type ArchetypeMap = HashMap<ArchetypeId, usize>;
@@ -14,9 +55,33 @@ type ArchetypeMap = HashMap<ArchetypeId, usize>;
 type ComponentType = BTreeSet<ComponentId>;
 
 type EntityId = usize;
-type ComponentId = TypeId;
 type ArchetypeId = usize;
 
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum ComponentId {
+    /// A plain Rust component type.
+    Type(TypeId),
+    /// A relation type paired with the entity it targets, e.g. `(TravelsTo, station)`.
+    Pair(TypeId, EntityId),
+    /// A component registered at runtime with [`World::register_dynamic_component`].
+    Dynamic(usize),
+}
+
+impl ComponentId {
+    pub fn of<T: Any>() -> Self {
+        Self::Type(TypeId::of::<T>())
+    }
+
+    pub fn pair<R: Any>(target: EntityId) -> Self {
+        Self::Pair(TypeId::of::<R>(), target)
+    }
+
+    /// Whether this is a pair of the relation `relation`, whatever its target.
+    fn is_pair_of(&self, relation: TypeId) -> bool {
+        matches!(self, Self::Pair(pair_relation, _) if *pair_relation == relation)
+    }
+}
+
 #[derive(Debug)]
 pub struct Column {
     components: AnyVec,
@@ -38,153 +103,432 @@ pub struct EntityRecord {
 
 pub struct EntityCreator<'a> {
     world: &'a mut World,
-    archetype: Archetype,
-    components_set: ComponentType,
+    components: HashMap<ComponentId, AnyVec>,
+    sparse_components: HashMap<ComponentId, AnyVec>,
 }
 
 impl EntityCreator<'_> {
-    pub fn with_component<T: Any>(mut self, component: T) -> Self {
-        let mut any_vec = AnyVec::new(Layout::array::<T>(1).unwrap());
-        any_vec.push(component);
+    /// Adds `component` to the entity. Adding a `T` a second time replaces, and drops, the
+    /// earlier value.
+    pub fn with_component<T: Any + Send + Sync>(self, component: T) -> Self {
+        self.with_component_id(ComponentId::of::<T>(), component)
+    }
 
-        let component_id = TypeId::of::<T>();
+    /// Like [`EntityCreator::with_component`], but refuses to add a `T` a second time.
+    pub fn try_with_component<T: Any + Send + Sync>(self, component: T) -> Result<Self, EcsError> {
+        if self.has_component_id(ComponentId::of::<T>()) {
+            return Err(EcsError::DuplicateComponent(type_name::<T>().to_string()));
+        }
 
-        self.archetype.columns.push(Column {
-            components: any_vec,
-        });
+        Ok(self.with_component(component))
+    }
 
-        self.archetype
-            .column_index
-            .insert(component_id, self.archetype.column_index.len());
+    /// Adds the relation pair `(relation, target)`. Ignored if `target` doesn't exist.
+    pub fn with_pair<R: Any + Send + Sync>(self, relation: R, target: EntityId) -> Self {
+        if !self.world.contains(target) {
+            return self;
+        }
 
-        self.components_set.insert(component_id);
+        self.with_component_id(ComponentId::pair::<R>(target), relation)
+    }
+
+    fn with_component_id<T: Any>(mut self, component_id: ComponentId, component: T) -> Self {
+        self.world.register_type_name::<T>();
+
+        let mut any_vec = column_for::<T>();
+        any_vec.push(component);
+
+        if self.world.sparse_sets.contains_key(&component_id) {
+            self.sparse_components.insert(component_id, any_vec);
+        } else {
+            self.components.insert(component_id, any_vec);
+        }
 
         self
     }
 
-    pub fn spawn(mut self) -> EntityRecord {
-        if let Some(archetype_id) = self.world.archetype_index.get(&self.components_set) {
-            let archetype = self.world.archetypes.get_mut(*archetype_id).unwrap();
+    fn has_component_id(&self, component_id: ComponentId) -> bool {
+        self.components.contains_key(&component_id)
+            || self.sparse_components.contains_key(&component_id)
+    }
 
-            let entity_record = EntityRecord {
-                archetype_id: *archetype_id,
-                id: self.world.entity_index.len(),
-                row: archetype.column_index.len(),
-            };
+    /// The archetype of the table components added so far, created if it doesn't exist yet.
+    fn archetype_id(&mut self) -> ArchetypeId {
+        // Columns are laid out in the canonical order of the component set, so the order of the
+        // `with_component` calls doesn't matter.
+        let components_set = self.components.keys().copied().collect::<ComponentType>();
+        let columns = self
+            .components
+            .iter()
+            .map(|(component_id, components)| (*component_id, components.empty_like()))
+            .collect();
 
-            archetype.entities.push(entity_record.id);
+        self.world.archetype_for(components_set, columns)
+    }
 
-            archetype
-                .column_index
-                .iter()
-                .for_each(|(component_id, index)| {
-                    let column = archetype.columns.get_mut(*index).unwrap();
-                    let component_index = self.archetype.column_index.get(component_id).unwrap();
-                    let component = self
-                        .archetype
-                        .columns
-                        .get(*component_index)
-                        .unwrap()
-                        .components
-                        .get_raw(*component_index)
-                        .unwrap();
+    /// Like [`EntityCreator::spawn`], but reports running out of memory for the entity's
+    /// storage instead of aborting, in which case its components are dropped.
+    pub fn try_spawn(mut self) -> Result<EntityRecord, EcsError> {
+        let archetype_id = self.archetype_id();
+        let entity = self.world.next_entity_id;
 
-                    unsafe { column.components.push_raw(component) };
-                });
+        let archetype = &mut self.world.archetypes[archetype_id];
+        archetype
+            .entities
+            .try_reserve(1)
+            .map_err(|_| EcsError::AllocationFailed)?;
+        archetype
+            .columns
+            .iter_mut()
+            .try_for_each(|column| column.components.try_reserve(1))?;
 
-            self.world
-                .entity_index
-                .insert(entity_record.id, entity_record);
+        self.sparse_components.keys().try_for_each(|component_id| {
+            let sparse_set = self.world.sparse_sets.get_mut(component_id).unwrap();
+            sparse_set.try_reserve_for(entity)
+        })?;
 
-            entity_record
-        } else {
-            let entity_id = self.world.entity_index.len();
-            let entity_record = EntityRecord {
-                id: entity_id,
-                archetype_id: self.world.archetypes.len(),
-                row: 0,
-            };
+        self.world
+            .entity_index
+            .try_reserve(1)
+            .map_err(|_| EcsError::AllocationFailed)?;
 
-            self.components_set
-                .iter()
-                .enumerate()
-                .for_each(|(index, component_id)| {
-                    self.world
-                        .component_index
-                        .entry(*component_id)
-                        .or_default()
-                        .insert(self.world.archetypes.len(), index);
-                });
+        Ok(self.spawn())
+    }
 
-            self.archetype.entities.push(entity_id);
-            self.world.entity_index.insert(entity_id, entity_record);
+    pub fn spawn(mut self) -> EntityRecord {
+        let component_ids = self
+            .components
+            .keys()
+            .chain(self.sparse_components.keys())
+            .copied()
+            .collect::<BTreeSet<_>>();
+
+        let archetype_id = self.archetype_id();
+        let archetype = &mut self.world.archetypes[archetype_id];
+        let entity_record = EntityRecord {
+            id: self.world.next_entity_id,
+            archetype_id,
+            row: archetype.entities.len(),
+        };
+        self.world.next_entity_id += 1;
 
-            self.world
-                .archetype_index
-                .insert(self.components_set, self.world.archetypes.len());
+        archetype
+            .column_index
+            .iter()
+            .for_each(|(component_id, column_id)| {
+                let components = self.components.get_mut(component_id).unwrap();
 
-            self.world.archetypes.push(self.archetype);
+                // The value now lives in the archetype, so the creator must not drop it.
+                unsafe {
+                    archetype.columns[*column_id]
+                        .components
+                        .push_raw(components.get_raw(0).unwrap());
+                    components.set_len(0);
+                }
+            });
+
+        archetype.entities.push(entity_record.id);
+        self.world
+            .entity_index
+            .insert(entity_record.id, entity_record);
+
+        self.sparse_components
+            .iter_mut()
+            .for_each(|(component_id, components)| {
+                let sparse_set = self.world.sparse_sets.get_mut(component_id).unwrap();
+
+                unsafe {
+                    sparse_set.insert_raw(entity_record.id, components.get_raw(0).unwrap());
+                    components.set_len(0);
+                }
+            });
+
+        self.world.deferred(|world| {
+            [Lifecycle::Add, Lifecycle::Insert]
+                .into_iter()
+                .for_each(|lifecycle| {
+                    component_ids.iter().for_each(|component_id| {
+                        world.trigger(entity_record.id, *component_id, lifecycle);
+                    });
+                });
+        });
 
-            entity_record
-        }
+        entity_record
     }
 }
 
 pub struct QueryCreator {
     component_ids: Vec<ComponentId>,
+    relations: Vec<TypeId>,
+    include_disabled: bool,
+    entity_order: bool,
 }
 
 impl QueryCreator {
     pub fn with_component<T: Any>(mut self) -> Self {
-        self.component_ids.push(TypeId::of::<T>());
+        self.component_ids.push(ComponentId::of::<T>());
+        self
+    }
+
+    /// Matches entities with the pair `(R, target)`.
+    pub fn with_pair<R: Any>(mut self, target: EntityId) -> Self {
+        self.component_ids.push(ComponentId::pair::<R>(target));
+        self
+    }
+
+    /// Matches entities with at least one `R` pair, whatever its target.
+    pub fn with_any_pair<R: Any>(mut self) -> Self {
+        self.relations.push(TypeId::of::<R>());
+        self
+    }
+
+    /// Also matches [`Disabled`] entities, which queries skip otherwise. Asking for the
+    /// `Disabled` component itself opts in as well.
+    pub fn with_disabled(mut self) -> Self {
+        self.include_disabled = true;
         self
     }
 
     pub fn iter<'w>(&self, world: &'w World) -> QueryIter<'w> {
-        let archetype_ids = self
-            .component_ids
-            .iter()
-            .map(|component_id| {
-                let Some(archetype_map) = world.component_index.get(component_id) else {
-                    return BTreeSet::new();
-                };
+        let mut results = self.storage_order(world);
 
-                archetype_map.keys().copied().collect::<BTreeSet<_>>()
-            })
-            .reduce(|a, b| a.intersection(&b).cloned().collect())
-            .unwrap_or_default()
-            .iter()
-            .cloned()
-            .collect();
+        if self.entity_order {
+            results.sort_by_entity();
+        }
+
+        results
+    }
+
+    /// Iterates in storage order whatever the query asks for, for lookups that don't need to
+    /// sort every match.
+    fn storage_order<'w>(&self, world: &'w World) -> QueryIter<'w> {
+        let (archetype_ids, table_ids, sparse_ids) = self.matches(world);
 
         QueryIter {
             world,
             entity_index: 0,
             archetype_index: 0,
             archetype_ids,
-            component_ids: self.component_ids.to_vec(),
+            component_ids: table_ids,
+            sparse_ids,
+            relations: self.relations.to_vec(),
+            order: None,
         }
     }
+
+    /// The only entity matching the query.
+    ///
+    /// # Panics
+    /// If no entity or more than one entity matches, see [`QueryCreator::get_single`].
+    pub fn single<'w>(&self, world: &'w World) -> QueryResult<'w> {
+        self.get_single(world)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    pub fn get_single<'w>(&self, world: &'w World) -> Result<QueryResult<'w>, EcsError> {
+        let mut results = self.storage_order(world);
+
+        match (results.next(), results.next()) {
+            (Some(result), None) => Ok(result),
+            (None, _) => Err(EcsError::NoMatchingEntity),
+            (Some(_), Some(_)) => Err(EcsError::MultipleMatchingEntities),
+        }
+    }
+
+    /// The components of `entity`, if it matches the query.
+    pub fn get<'w>(&self, world: &'w World, entity: EntityId) -> Result<QueryResult<'w>, EcsError> {
+        let entity_record = world.try_entity(entity)?;
+        let results = self.storage_order(world);
+
+        if !results.archetype_ids.contains(&entity_record.archetype_id) {
+            return Err(EcsError::QueryMismatch(entity));
+        }
+
+        results
+            .fetch(
+                &world.archetypes[entity_record.archetype_id],
+                entity_record.row,
+            )
+            .ok_or(EcsError::QueryMismatch(entity))
+    }
+
+    /// Like [`QueryCreator::get`], but borrows the world exclusively while the result lives.
+    pub fn get_mut<'w>(
+        &self,
+        world: &'w mut World,
+        entity: EntityId,
+    ) -> Result<QueryResult<'w>, EcsError> {
+        self.get(world, entity)
+    }
+
+    /// The components of several distinct entities at once, e.g. to update two trains together.
+    /// Asking for the same entity twice is a [`EcsError::BorrowConflict`].
+    pub fn get_many_mut<'w, const N: usize>(
+        &self,
+        world: &'w mut World,
+        entities: [EntityId; N],
+    ) -> Result<[QueryResult<'w>; N], EcsError> {
+        if let Some(index) = (1..N).find(|index| entities[..*index].contains(&entities[*index])) {
+            return Err(EcsError::BorrowConflict(format!(
+                "entity {}",
+                entities[index]
+            )));
+        }
+
+        let world = &*world;
+        let mut results = entities
+            .iter()
+            .map(|entity| self.get(world, *entity))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter();
+
+        Ok(array::from_fn(|_| results.next().unwrap()))
+    }
+
+    /// Iterates over the matched archetypes, yielding each one as a [`Chunk`] of column slices.
+    ///
+    /// # Panics
+    /// If the query asks for a sparse-set component, see [`QueryCreator::try_iter_chunks`].
+    pub fn iter_chunks<'w>(&self, world: &'w World) -> ChunkIter<'w> {
+        self.try_iter_chunks(world)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Like [`QueryCreator::iter_chunks`], but fails if the query asks for a sparse-set
+    /// component. Those live outside the archetype tables, so only some rows of a chunk might
+    /// have them.
+    pub fn try_iter_chunks<'w>(&self, world: &'w World) -> Result<ChunkIter<'w>, EcsError> {
+        let (archetype_ids, _, sparse_ids) = self.matches(world);
+
+        if let Some(component_id) = sparse_ids.first() {
+            return Err(EcsError::SparseChunkComponent(
+                world.component_name(*component_id),
+            ));
+        }
+
+        Ok(ChunkIter::new(world, archetype_ids))
+    }
+
+    /// Splits the requested components into table and sparse-set components, and finds the
+    /// archetypes storing every requested table component and relation.
+    fn matches(&self, world: &World) -> (Vec<ArchetypeId>, Vec<ComponentId>, Vec<ComponentId>) {
+        let (sparse_ids, table_ids): (Vec<_>, Vec<_>) = self
+            .component_ids
+            .iter()
+            .partition(|component_id| world.sparse_sets.contains_key(component_id));
+
+        let components = table_ids.iter().map(|component_id| {
+            let Some(archetype_map) = world.component_index.get(component_id) else {
+                return BTreeSet::new();
+            };
+
+            archetype_map.keys().copied().collect::<BTreeSet<_>>()
+        });
+
+        let relations = self.relations.iter().map(|relation| {
+            world
+                .component_index
+                .iter()
+                .filter(|(component_id, _)| component_id.is_pair_of(*relation))
+                .flat_map(|(_, archetype_map)| archetype_map.keys().copied())
+                .collect::<BTreeSet<_>>()
+        });
+
+        let archetype_ids = components
+            .chain(relations)
+            .reduce(|a, b| a.intersection(&b).cloned().collect())
+            .unwrap_or_else(|| {
+                // Only sparse components were requested, so any archetype can match.
+                if sparse_ids.is_empty() {
+                    BTreeSet::new()
+                } else {
+                    (0..world.archetypes.len()).collect()
+                }
+            })
+            .into_iter()
+            .filter(|archetype_id| {
+                self.include_disabled
+                    || table_ids.contains(&ComponentId::of::<Disabled>())
+                    || !world.archetypes[*archetype_id]
+                        .column_index
+                        .contains_key(&ComponentId::of::<Disabled>())
+            })
+            .collect();
+
+        (archetype_ids, table_ids, sparse_ids)
+    }
 }
 
 pub struct QueryResult<'a> {
+    world: &'a World,
+    entity: EntityId,
     entity_components: HashMap<ComponentId, &'a mut u8>,
 }
 
 impl QueryResult<'_> {
+    /// The entity the components belong to.
+    pub fn entity(&self) -> EntityId {
+        self.entity
+    }
+
     pub fn get<T: Any>(&self) -> Option<&T> {
-        let component_id = TypeId::of::<T>();
+        self.get_by_id(ComponentId::of::<T>())
+    }
+
+    pub fn get_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.get_mut_by_id(ComponentId::of::<T>())
+    }
+
+    pub fn get_pair<R: Any>(&self, target: EntityId) -> Option<&R> {
+        self.get_by_id(ComponentId::pair::<R>(target))
+    }
+
+    pub fn get_pair_mut<R: Any>(&mut self, target: EntityId) -> Option<&mut R> {
+        self.get_mut_by_id(ComponentId::pair::<R>(target))
+    }
+
+    /// The targets of every `R` pair fetched by the query, in ascending order.
+    pub fn targets<R: Any>(&self) -> Vec<EntityId> {
+        let relation = TypeId::of::<R>();
+
+        let mut targets = self
+            .entity_components
+            .keys()
+            .filter_map(|component_id| match component_id {
+                ComponentId::Pair(pair_relation, target) if *pair_relation == relation => {
+                    Some(*target)
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        targets.sort_unstable();
+        targets
+    }
+
+    fn get_by_id<T: Any>(&self, component_id: ComponentId) -> Option<&T> {
         let component = self.entity_components.get(&component_id)?;
         let component = unsafe { &(*(*component as *const u8 as *const T)) };
         Some(component)
     }
 
-    pub fn get_mut<T: Any>(&mut self) -> Option<&mut T> {
-        let component_id = TypeId::of::<T>();
+    fn get_mut_by_id<T: Any>(&mut self, component_id: ComponentId) -> Option<&mut T> {
         let component = self.entity_components.get_mut(&component_id)?;
         let component = unsafe { &mut (*(*component as *mut u8 as *mut T)) };
+        self.world.mark_changed(self.entity, component_id);
         Some(component)
     }
+
+    pub fn get_raw(&self, component_id: ComponentId) -> Option<*const u8> {
+        let component = self.entity_components.get(&component_id)?;
+        Some(*component as *const u8)
+    }
+
+    pub fn get_mut_raw(&mut self, component_id: ComponentId) -> Option<*mut u8> {
+        let component = self.entity_components.get_mut(&component_id)?;
+        self.world.mark_changed(self.entity, component_id);
+        Some(*component as *mut u8)
+    }
 }
 
 pub struct QueryIter<'w> {
@@ -193,18 +537,84 @@ pub struct QueryIter<'w> {
     archetype_index: usize,
     archetype_ids: Vec<ArchetypeId>,
     component_ids: Vec<ComponentId>,
+    sparse_ids: Vec<ComponentId>,
+    relations: Vec<TypeId>,
+    /// The `(archetype, row)` positions left to visit, when iterating in a fixed order.
+    order: Option<vec::IntoIter<(ArchetypeId, usize)>>,
+}
+
+impl<'a> QueryIter<'a> {
+    /// Whether the entity at `row` has every requested sparse-set component.
+    fn has_sparse_components(&self, archetype: &Archetype, row: usize) -> bool {
+        let entity = archetype.entities[row];
+
+        self.sparse_ids
+            .iter()
+            .all(|component_id| self.world.sparse_sets[component_id].contains(entity))
+    }
+
+    /// Fetches the components of the entity at `row`, or `None` if it lacks one of the
+    /// requested sparse-set components.
+    fn fetch(&self, archetype: &'a Archetype, row: usize) -> Option<QueryResult<'a>> {
+        if !self.has_sparse_components(archetype, row) {
+            return None;
+        }
+
+        let entity = archetype.entities[row];
+        let sparse = self.sparse_ids.iter().map(|component_id| {
+            let sparse_set = &self.world.sparse_sets[component_id];
+
+            (*component_id, unsafe {
+                &mut *(sparse_set.get_raw(entity).unwrap() as *mut u8)
+            })
+        });
+
+        let pairs = archetype.column_index.keys().filter(|component_id| {
+            self.relations
+                .iter()
+                .any(|relation| component_id.is_pair_of(*relation))
+        });
+
+        let entity_components = self
+            .component_ids
+            .iter()
+            .chain(pairs)
+            .map(|component_id| {
+                let components =
+                    &archetype.columns[archetype.column_index[component_id]].components;
+
+                (*component_id, unsafe {
+                    &mut *(components.get_raw(row).unwrap() as *mut u8)
+                })
+            })
+            .chain(sparse)
+            .collect();
+
+        Some(QueryResult {
+            world: self.world,
+            entity,
+            entity_components,
+        })
+    }
 }
 
 impl<'a> Iterator for QueryIter<'a> {
     type Item = QueryResult<'a>;
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(order) = &mut self.order {
+            let (archetype_id, row) = order.next()?;
+            let world = self.world;
+            return self.fetch(&world.archetypes[archetype_id], row);
+        }
+
         loop {
             if self.archetype_index >= self.archetype_ids.len() {
                 return None;
             }
 
+            let world = self.world;
             let archetype = unsafe {
-                self.world
+                world
                     .archetypes
                     .get_unchecked(self.archetype_ids[self.archetype_index])
             };
@@ -215,108 +625,750 @@ impl<'a> Iterator for QueryIter<'a> {
                 continue;
             }
 
-            let entity_components = self
-                .component_ids
-                .iter()
-                .map(|component_id| {
-                    let components =
-                        &archetype.columns[archetype.column_index[component_id]].components;
+            let row = self.entity_index;
+            self.entity_index += 1;
+
+            if let Some(result) = self.fetch(archetype, row) {
+                return Some(result);
+            }
+        }
+    }
+}
+
+fn column_for<T: Any>() -> AnyVec {
+    let drop = needs_drop::<T>().then_some(drop_ptr::<T> as DropFn);
+    AnyVec::with_drop(Layout::new::<T>(), drop)
+}
+
+/// Borrows two distinct archetypes mutably at the same time.
+fn archetype_pair_mut(
+    archetypes: &mut [Archetype],
+    a: ArchetypeId,
+    b: ArchetypeId,
+) -> (&mut Archetype, &mut Archetype) {
+    assert_ne!(a, b, "an archetype can't be borrowed twice");
+
+    if a < b {
+        let (left, right) = archetypes.split_at_mut(b);
+        (&mut left[a], &mut right[0])
+    } else {
+        let (left, right) = archetypes.split_at_mut(a);
+        (&mut right[0], &mut left[b])
+    }
+}
+
+#[derive(Default)]
+pub struct World {
+    pub archetypes: Vec<Archetype>,
+    pub resources: Resources,
+    non_send_resources: NonSendResources,
+    pub entity_index: HashMap<EntityId, EntityRecord>,
+    pub archetype_index: HashMap<ComponentType, ArchetypeId>,
+    pub component_index: HashMap<ComponentId, ArchetypeMap>,
+    sparse_sets: HashMap<ComponentId, SparseSet>,
+    dynamic_components: Vec<ComponentInfo>,
+    type_names: HashMap<TypeId, &'static str>,
+    clone_fns: HashMap<TypeId, CloneFn>,
+    hash_fns: HashMap<TypeId, HashFn>,
+    indexes: HashMap<ComponentId, Box<dyn AnyIndex>>,
+    changed: Mutex<HashSet<(ComponentId, EntityId)>>,
+    free_archetypes: Vec<ArchetypeId>,
+    /// Archetypes made room for by [`World::reserve_for`] that automatic maintenance keeps.
+    reserved: HashSet<ArchetypeId>,
+    maintenance_interval: Option<usize>,
+    structural_changes: usize,
+    hooks: HashMap<ComponentId, ComponentHooks>,
+    /// The entities standing for shared values, by value type.
+    shared_values: HashMap<TypeId, Vec<EntityId>>,
+    observers: HashMap<EntityId, Vec<Observer>>,
+    commands: Commands,
+    command_depth: usize,
+    next_entity_id: EntityId,
+}
+
+// Components and resources are required to be `Send + Sync`, as are hooks and commands. The
+// type-erased columns hide that from the compiler, and non-Send resources refuse to be reached
+// from any other thread than the one that created the world.
+unsafe impl Send for World {}
+
+impl World {
+    /// Adds `resource`, replacing any existing `T`. See [`World::insert_resource`] to get the
+    /// replaced value back, or [`World::init_resource`] to keep it.
+    pub fn add_resource<T: Any + Send + Sync>(&mut self, resource: T) {
+        self.resources.add(resource);
+    }
+
+    /// Adds `resource` and returns the `T` it replaced, if any.
+    pub fn insert_resource<T: Any + Send + Sync>(&mut self, resource: T) -> Option<T> {
+        self.resources.insert(resource)
+    }
+
+    /// Adds the default `T` resource, unless the world already has one.
+    pub fn init_resource<T: Any + Send + Sync + Default>(&mut self) {
+        self.resources.init::<T>();
+    }
+
+    /// Panics if the resource is borrowed mutably, see [`World::try_get_resource`].
+    pub fn get_resource<T: Any>(&self) -> Option<Res<'_, T>> {
+        self.resources.get_ref::<T>()
+    }
+
+    pub fn get_resource_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.resources.get_mut::<T>()
+    }
+
+    /// Borrows a resource mutably through a shared reference, so it can be held while querying
+    /// the world. Panics if the resource is already borrowed, see
+    /// [`World::try_borrow_resource_mut`].
+    pub fn borrow_resource_mut<T: Any>(&self) -> Option<ResMut<'_, T>> {
+        self.resources.borrow_mut::<T>()
+    }
+
+    /// Takes the `T` resource out of the world for the duration of `scope`, so it can be used
+    /// alongside full access to the world. The resource is put back afterwards, even if `scope`
+    /// panics, replacing any `T` added by `scope`. Panics if there is no `T` resource.
+    pub fn resource_scope<T: Any + Send + Sync, R>(
+        &mut self,
+        scope: impl FnOnce(&mut World, &mut T) -> R,
+    ) -> R {
+        /// Puts the resource back when dropped, which also happens while unwinding.
+        struct Restore<'w, T: Any + Send + Sync> {
+            world: &'w mut World,
+            resource: Option<T>,
+            added: Option<Tick>,
+        }
+
+        impl<T: Any + Send + Sync> Drop for Restore<'_, T> {
+            fn drop(&mut self) {
+                if let Some(resource) = self.resource.take() {
+                    self.world.resources.restore(resource, self.added);
+                }
+            }
+        }
+
+        let added = self.resources.added_tick::<T>();
+        let resource = self
+            .resources
+            .take::<T>()
+            .unwrap_or_else(|| panic!("{}", EcsError::missing_resource::<T>()));
+
+        let mut restore = Restore {
+            world: self,
+            resource: Some(resource),
+            added,
+        };
+        scope(restore.world, restore.resource.as_mut().unwrap())
+    }
+
+    pub fn delete_resource<T: Any>(&mut self) {
+        self.resources.delete::<T>();
+    }
+
+    /// Adds a resource that isn't `Send`, returning the one it replaced. It can only be reached
+    /// from the thread that created the world, see [`NonSendResources`].
+    pub fn insert_non_send_resource<T: Any>(&mut self, resource: T) -> Option<T> {
+        self.non_send_resources.insert(resource)
+    }
+
+    pub fn get_non_send_resource<T: Any>(&self) -> Option<&T> {
+        self.non_send_resources.get_ref()
+    }
+
+    pub fn get_non_send_resource_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.non_send_resources.get_mut()
+    }
+
+    pub fn remove_non_send_resource<T: Any>(&mut self) -> Option<T> {
+        self.non_send_resources.take()
+    }
+
+    /// The latest resource tick. Store it to later ask whether a resource changed since.
+    pub fn change_tick(&self) -> Tick {
+        self.resources.tick()
+    }
+
+    /// Whether the `T` resource was added after `tick`. False if there's no `T` resource.
+    pub fn is_resource_added<T: Any>(&self, tick: Tick) -> bool {
+        self.resources
+            .added_tick::<T>()
+            .is_some_and(|added| added > tick)
+    }
+
+    /// Whether the `T` resource was added, replaced or mutably accessed after `tick`.
+    pub fn is_resource_changed<T: Any>(&self, tick: Tick) -> bool {
+        self.resources
+            .changed_tick::<T>()
+            .is_some_and(|changed| changed > tick)
+    }
+}
+
+impl World {
+    pub fn has_component<T: Any>(&self, entity: EntityId) -> bool {
+        self.has_component_id(entity, ComponentId::of::<T>())
+    }
+
+    pub fn get_component<T: Any>(&self, entity: EntityId) -> Option<&T> {
+        self.get_component_by_id(entity, ComponentId::of::<T>())
+    }
+
+    pub fn get_component_mut<T: Any>(&mut self, entity: EntityId) -> Option<&mut T> {
+        self.get_component_mut_by_id(entity, ComponentId::of::<T>())
+    }
+
+    /// Chooses where `T` components are stored. The storage can only be changed while no
+    /// entity has a `T`; returns false otherwise.
+    pub fn set_storage_type<T: Any>(&mut self, storage_type: StorageType) -> bool {
+        let component_id = ComponentId::of::<T>();
+
+        // Queries filter disabled entities by archetype, so the marker has to stay in the tables.
+        if component_id == ComponentId::of::<Disabled>() {
+            return storage_type == StorageType::Table;
+        }
+
+        if self.storage_type::<T>() == storage_type {
+            return true;
+        }
+
+        match storage_type {
+            StorageType::SparseSet => {
+                let in_tables =
+                    self.component_index
+                        .get(&component_id)
+                        .is_some_and(|archetype_map| {
+                            archetype_map.keys().any(|archetype_id| {
+                                !self.archetypes[*archetype_id].entities.is_empty()
+                            })
+                        });
+
+                if in_tables {
+                    return false;
+                }
+
+                self.sparse_sets
+                    .insert(component_id, SparseSet::new(column_for::<T>()));
+            }
+            StorageType::Table => {
+                if !self.sparse_sets[&component_id].is_empty() {
+                    return false;
+                }
+
+                self.sparse_sets.remove(&component_id);
+            }
+        }
+
+        true
+    }
+
+    pub fn storage_type<T: Any>(&self) -> StorageType {
+        if self.sparse_sets.contains_key(&ComponentId::of::<T>()) {
+            StorageType::SparseSet
+        } else {
+            StorageType::Table
+        }
+    }
+
+    fn has_component_id(&self, entity: EntityId, component_id: ComponentId) -> bool {
+        if let Some(sparse_set) = self.sparse_sets.get(&component_id) {
+            return sparse_set.contains(entity);
+        }
+
+        let Some(entity_record) = self.entity_index.get(&entity) else {
+            return false;
+        };
+
+        let Some(archetype_map) = self.component_index.get(&component_id) else {
+            return false;
+        };
+
+        archetype_map.contains_key(&entity_record.archetype_id)
+    }
+
+    fn get_component_by_id<T: Any>(
+        &self,
+        entity: EntityId,
+        component_id: ComponentId,
+    ) -> Option<&T> {
+        if let Some(sparse_set) = self.sparse_sets.get(&component_id) {
+            return sparse_set.get(entity);
+        }
+
+        let entity_record = self.entity_index.get(&entity)?;
+        let archetype = self.archetypes.get(entity_record.archetype_id)?;
+
+        let archetype_map = self.component_index.get(&component_id)?;
+        let column_id = archetype_map.get(&entity_record.archetype_id)?;
+
+        archetype
+            .columns
+            .get(*column_id)?
+            .components
+            .get::<T>(entity_record.row)
+    }
+
+    fn get_component_mut_by_id<T: Any>(
+        &mut self,
+        entity: EntityId,
+        component_id: ComponentId,
+    ) -> Option<&mut T> {
+        self.mark_changed(entity, component_id);
+
+        if let Some(sparse_set) = self.sparse_sets.get_mut(&component_id) {
+            return sparse_set.get_mut(entity);
+        }
+
+        let entity_record = self.entity_index.get(&entity)?;
+        let archetype = self.archetypes.get_mut(entity_record.archetype_id)?;
+
+        let archetype_map = self.component_index.get(&component_id)?;
+        let column_id = archetype_map.get(&entity_record.archetype_id)?;
+
+        archetype
+            .columns
+            .get_mut(*column_id)?
+            .components
+            .get_mut::<T>(entity_record.row)
+    }
+
+    pub fn contains(&self, entity: EntityId) -> bool {
+        self.entity_index.contains_key(&entity)
+    }
+
+    /// Adds `component` to `entity`, replacing (and dropping) the previous value if the entity
+    /// already has a `T`. Does nothing if the entity doesn't exist.
+    pub fn insert_component<T: Any + Send + Sync>(&mut self, entity: EntityId, component: T) {
+        self.insert_component_by_id(entity, ComponentId::of::<T>(), component);
+    }
+
+    /// Removes the `T` component from `entity` and returns it.
+    pub fn remove_component<T: Any>(&mut self, entity: EntityId) -> Option<T> {
+        self.remove_component_by_id(entity, ComponentId::of::<T>())
+    }
+
+    fn insert_component_by_id<T: Any>(
+        &mut self,
+        entity: EntityId,
+        component_id: ComponentId,
+        component: T,
+    ) {
+        if !self.contains(entity) {
+            return;
+        }
+
+        self.register_type_name::<T>();
+
+        self.deferred(|world| {
+            let added = !world.has_component_id(entity, component_id);
+            world.store_component(entity, component_id, component);
+
+            if added {
+                world.trigger(entity, component_id, Lifecycle::Add);
+            }
+            world.trigger(entity, component_id, Lifecycle::Insert);
+        });
+    }
+
+    fn remove_component_by_id<T: Any>(
+        &mut self,
+        entity: EntityId,
+        component_id: ComponentId,
+    ) -> Option<T> {
+        if !self.has_component_id(entity, component_id) {
+            return None;
+        }
+
+        self.deferred(|world| {
+            world.trigger(entity, component_id, Lifecycle::Remove);
+            world.take_component(entity, component_id)
+        })
+    }
+
+    /// Stores `component` in the right storage without running any hooks.
+    fn store_component<T: Any>(
+        &mut self,
+        entity: EntityId,
+        component_id: ComponentId,
+        component: T,
+    ) {
+        // The storage takes ownership of the value, and drops it when it's replaced or removed.
+        let component = ManuallyDrop::new(component);
+
+        unsafe {
+            self.store_component_raw(
+                entity,
+                component_id,
+                &*component as *const T as *const u8,
+                column_for::<T>(),
+            )
+        };
+    }
+
+    /// Stores the value behind `component` like [`World::store_component_raw`], then runs the
+    /// hooks.
+    ///
+    /// # Safety
+    /// Same as [`World::store_component_raw`].
+    pub(crate) unsafe fn insert_component_with_column(
+        &mut self,
+        entity: EntityId,
+        component_id: ComponentId,
+        component: *const u8,
+        column: AnyVec,
+    ) {
+        self.deferred(|world| {
+            let added = !world.has_component_id(entity, component_id);
+            unsafe { world.store_component_raw(entity, component_id, component, column) };
+
+            if added {
+                world.trigger(entity, component_id, Lifecycle::Add);
+            }
+            world.trigger(entity, component_id, Lifecycle::Insert);
+        });
+    }
+
+    /// Moves the value behind `component` into the right storage without running any hooks.
+    /// `column` is an empty column for the component, used if the entity has to move to a new
+    /// archetype.
+    ///
+    /// # Safety
+    /// `component` must point to a valid value of the component, which must not be used or
+    /// dropped by the caller afterwards.
+    unsafe fn store_component_raw(
+        &mut self,
+        entity: EntityId,
+        component_id: ComponentId,
+        component: *const u8,
+        column: AnyVec,
+    ) {
+        let entity_record = self.entity_index[&entity];
+
+        if let Some(sparse_set) = self.sparse_sets.get_mut(&component_id) {
+            unsafe { sparse_set.insert_raw(entity, component) };
+            return;
+        }
+
+        let archetype = &mut self.archetypes[entity_record.archetype_id];
+
+        if let Some(column_id) = archetype.column_index.get(&component_id) {
+            unsafe {
+                archetype.columns[*column_id]
+                    .components
+                    .replace_raw(entity_record.row, component)
+            };
+            return;
+        }
+
+        let mut columns = self.empty_columns(entity_record.archetype_id);
+        columns.insert(component_id, column);
+
+        let components_set = columns.keys().copied().collect();
+        let archetype_id = self.archetype_for(components_set, columns);
+        self.move_entity(entity, archetype_id, true);
+
+        let archetype = &mut self.archetypes[archetype_id];
+        unsafe {
+            archetype.columns[archetype.column_index[&component_id]]
+                .components
+                .push_raw(component)
+        };
+    }
+
+    /// Moves the component out of its storage without running any hooks.
+    fn take_component<T: Any>(&mut self, entity: EntityId, component_id: ComponentId) -> Option<T> {
+        if let Some(sparse_set) = self.sparse_sets.get_mut(&component_id) {
+            return sparse_set.remove(entity);
+        }
+
+        let entity_record = self.entity_index.get(&entity).copied()?;
+
+        let archetype = &self.archetypes[entity_record.archetype_id];
+        let column_id = archetype.column_index.get(&component_id)?;
+
+        let component = archetype.columns[*column_id]
+            .components
+            .get_raw(entity_record.row)
+            .unwrap();
+        let component = unsafe { read(component as *const T) };
+
+        // The removed value has been read out above, so it must be forgotten, not dropped.
+        self.remove_column(entity, component_id, false);
 
-                    (*component_id, unsafe {
-                        &mut *(components.get_raw(self.entity_index).unwrap() as *mut u8)
-                    })
-                })
-                .collect();
+        Some(component)
+    }
 
-            self.entity_index += 1;
-            return Some(QueryResult { entity_components });
+    /// Moves `entity` to the archetype without `component_id`, dropping the removed value if
+    /// `drop_removed` is true. Returns false if the entity doesn't have the component.
+    fn remove_column(
+        &mut self,
+        entity: EntityId,
+        component_id: ComponentId,
+        drop_removed: bool,
+    ) -> bool {
+        if let Some(sparse_set) = self.sparse_sets.get_mut(&component_id) {
+            return sparse_set.remove_and_drop(entity);
+        }
+
+        let Some(entity_record) = self.entity_index.get(&entity).copied() else {
+            return false;
+        };
+
+        let mut columns = self.empty_columns(entity_record.archetype_id);
+        if columns.remove(&component_id).is_none() {
+            return false;
         }
+
+        let components_set = columns.keys().copied().collect();
+        let archetype_id = self.archetype_for(components_set, columns);
+        self.move_entity(entity, archetype_id, drop_removed);
+
+        true
     }
-}
 
-#[derive(Default)]
-pub struct World {
-    pub archetypes: Vec<Archetype>,
-    pub resources: Resources,
-    pub entity_index: HashMap<EntityId, EntityRecord>,
-    pub archetype_index: HashMap<ComponentType, ArchetypeId>,
-    pub component_index: HashMap<ComponentId, ArchetypeMap>,
-}
+    /// Removes `entity` and drops all of its components. Its children are orphaned, see
+    /// [`World::despawn_recursive`] to remove them as well.
+    pub fn despawn(&mut self, entity: EntityId) -> bool {
+        if !self.contains(entity) {
+            return false;
+        }
 
-impl World {
-    pub fn add_resource<T: Any>(&mut self, resource: T) {
-        self.resources.add(resource);
+        self.despawn_entity(entity, true);
+        true
     }
 
-    pub fn get_resource<T: Any>(&self) -> Option<&T> {
-        self.resources.get_ref::<T>()
+    /// Runs the despawn of an existing `entity`. If `drop_components` is false its remaining
+    /// components are forgotten instead, as they have been moved elsewhere.
+    fn despawn_entity(&mut self, entity: EntityId, drop_components: bool) {
+        self.deferred(|world| {
+            world.detach_hierarchy(entity);
+            world.remove_pairs_targeting(entity);
+
+            world
+                .component_ids(entity)
+                .into_iter()
+                .for_each(|component_id| {
+                    world.trigger(entity, component_id, Lifecycle::Remove);
+                });
+
+            world.sparse_sets.values_mut().for_each(|sparse_set| {
+                if drop_components {
+                    sparse_set.remove_and_drop(entity);
+                } else {
+                    unsafe { sparse_set.remove_and_forget(entity) };
+                }
+            });
+
+            let entity_record = world.entity_index.remove(&entity).unwrap();
+            let archetype = &mut world.archetypes[entity_record.archetype_id];
+
+            archetype.columns.iter_mut().for_each(|column| {
+                if drop_components {
+                    column.components.swap_remove_drop(entity_record.row);
+                } else {
+                    unsafe { column.components.swap_remove_forget(entity_record.row) };
+                }
+            });
+
+            archetype.entities.swap_remove(entity_record.row);
+            if let Some(moved) = archetype.entities.get(entity_record.row) {
+                world.entity_index.get_mut(moved).unwrap().row = entity_record.row;
+            }
+
+            world.observers.remove(&entity);
+            world.structural_changes += 1;
+        });
     }
 
-    pub fn get_resource_mut<T: Any>(&mut self) -> Option<&mut T> {
-        self.resources.get_mut::<T>()
+    /// Every component of `entity`, in both table and sparse-set storage.
+    fn component_ids(&self, entity: EntityId) -> BTreeSet<ComponentId> {
+        let Some(entity_record) = self.entity_index.get(&entity) else {
+            return BTreeSet::new();
+        };
+
+        let sparse = self
+            .sparse_sets
+            .iter()
+            .filter(|(_, sparse_set)| sparse_set.contains(entity))
+            .map(|(component_id, _)| *component_id);
+
+        self.archetypes[entity_record.archetype_id]
+            .column_index
+            .keys()
+            .copied()
+            .chain(sparse)
+            .collect()
     }
 
-    pub fn delete_resource<T: Any>(&mut self) {
-        self.resources.delete::<T>();
+    /// Remembers the name of `T`, as `TypeId`s can't be turned back into one.
+    fn register_type_name<T: Any>(&mut self) {
+        self.type_names
+            .entry(TypeId::of::<T>())
+            .or_insert_with(type_name::<T>);
     }
-}
 
-impl World {
-    pub fn has_component<T: Any>(&self, entity: EntityId) -> bool {
-        let Some(entity_record) = self.entity_index.get(&entity) else {
-            return false;
+    /// A readable name for the component, e.g. `grid::Tile` or `(grid::TravelsTo, 4)` for pairs.
+    pub(crate) fn component_name(&self, component_id: ComponentId) -> String {
+        let type_name = |type_id| {
+            self.type_names
+                .get(&type_id)
+                .copied()
+                .unwrap_or("<unknown>")
         };
 
-        let Some(archetype_map) = self.component_index.get(&TypeId::of::<T>()) else {
-            return false;
+        match component_id {
+            ComponentId::Type(type_id) => type_name(type_id).to_string(),
+            ComponentId::Pair(relation, target) => format!("({}, {target})", type_name(relation)),
+            ComponentId::Dynamic(index) => self.dynamic_components[index].name().to_string(),
+        }
+    }
+
+    /// Creates an empty column for every component of the given archetype.
+    fn empty_columns(&self, archetype_id: ArchetypeId) -> HashMap<ComponentId, AnyVec> {
+        let archetype = &self.archetypes[archetype_id];
+
+        archetype
+            .column_index
+            .iter()
+            .map(|(component_id, column_id)| {
+                (
+                    *component_id,
+                    archetype.columns[*column_id].components.empty_like(),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the archetype storing exactly `components_set`, creating it out of `columns` if
+    /// it doesn't exist yet.
+    fn archetype_for(
+        &mut self,
+        components_set: ComponentType,
+        mut columns: HashMap<ComponentId, AnyVec>,
+    ) -> ArchetypeId {
+        if let Some(archetype_id) = self.archetype_index.get(&components_set) {
+            return *archetype_id;
+        }
+
+        // Reuse the slot of an archetype retired by `shrink_to_fit`, if there is one.
+        let archetype_id = self.free_archetypes.pop().unwrap_or(self.archetypes.len());
+        let mut archetype = Archetype {
+            columns: vec![],
+            entities: Vec::new(),
+            column_index: HashMap::new(),
         };
 
-        archetype_map.contains_key(&entity_record.archetype_id)
+        components_set
+            .iter()
+            .enumerate()
+            .for_each(|(index, component_id)| {
+                archetype.columns.push(Column {
+                    components: columns.remove(component_id).unwrap(),
+                });
+                archetype.column_index.insert(*component_id, index);
+
+                self.component_index
+                    .entry(*component_id)
+                    .or_default()
+                    .insert(archetype_id, index);
+            });
+
+        self.archetype_index.insert(components_set, archetype_id);
+        if archetype_id == self.archetypes.len() {
+            self.archetypes.push(archetype);
+        } else {
+            self.archetypes[archetype_id] = archetype;
+        }
+
+        archetype_id
     }
 
-    pub fn get_component<T: Any>(&self, entity: EntityId) -> Option<&T> {
-        let entity_record = self.entity_index.get(&entity)?;
-        let archetype = self.archetypes.get(entity_record.archetype_id)?;
+    /// Moves `entity` to the archetype `to`, carrying over the components both archetypes
+    /// share. Components `to` doesn't store are dropped, or forgotten if `drop_removed` is
+    /// false. Components only `to` stores must be pushed by the caller afterwards.
+    fn move_entity(&mut self, entity: EntityId, to: ArchetypeId, drop_removed: bool) {
+        let entity_record = self.entity_index[&entity];
+        let row = entity_record.row;
+        self.structural_changes += 1;
 
-        let archetype_map = self.component_index.get(&TypeId::of::<T>())?;
-        let column_id = archetype_map.get(&entity_record.archetype_id)?;
+        let (source, destination) =
+            archetype_pair_mut(&mut self.archetypes, entity_record.archetype_id, to);
 
-        archetype
-            .columns
-            .get(*column_id)?
-            .components
-            .get::<T>(entity_record.row)
+        source
+            .column_index
+            .iter()
+            .for_each(|(component_id, column_id)| {
+                let components = &mut source.columns[*column_id].components;
+
+                match destination.column_index.get(component_id) {
+                    Some(destination_column) => unsafe {
+                        destination.columns[*destination_column]
+                            .components
+                            .push_raw(components.get_raw(row).unwrap());
+                        components.swap_remove_forget(row);
+                    },
+                    None if drop_removed => components.swap_remove_drop(row),
+                    None => unsafe { components.swap_remove_forget(row) },
+                }
+            });
+
+        source.entities.swap_remove(row);
+        if let Some(moved) = source.entities.get(row) {
+            self.entity_index.get_mut(moved).unwrap().row = row;
+        }
+
+        destination.entities.push(entity);
+        let entity_record = self.entity_index.get_mut(&entity).unwrap();
+        entity_record.archetype_id = to;
+        entity_record.row = destination.entities.len() - 1;
     }
 
-    pub fn get_component_mut<T: Any>(&mut self, entity: EntityId) -> Option<&mut T> {
-        let entity_record = self.entity_index.get(&entity)?;
-        let archetype = self.archetypes.get_mut(entity_record.archetype_id)?;
+    /// Moves every entity of the `from` archetype to `to` at once, like [`World::move_entity`]
+    /// does for a single row.
+    fn move_table(&mut self, from: ArchetypeId, to: ArchetypeId, drop_removed: bool) {
+        self.structural_changes += 1;
 
-        let archetype_map = self.component_index.get(&TypeId::of::<T>())?;
-        let column_id = archetype_map.get(&entity_record.archetype_id)?;
+        let (source, destination) = archetype_pair_mut(&mut self.archetypes, from, to);
 
-        archetype
-            .columns
-            .get_mut(*column_id)?
-            .components
-            .get_mut::<T>(entity_record.row)
+        source
+            .column_index
+            .iter()
+            .for_each(|(component_id, column_id)| {
+                let components = &mut source.columns[*column_id].components;
+
+                match destination.column_index.get(component_id) {
+                    Some(destination_column) => destination.columns[*destination_column]
+                        .components
+                        .append(components),
+                    None if drop_removed => components.clear(),
+                    None => unsafe { components.set_len(0) },
+                }
+            });
+
+        let first_row = destination.entities.len();
+        destination.entities.append(&mut source.entities);
+
+        destination.entities[first_row..]
+            .iter()
+            .enumerate()
+            .for_each(|(offset, entity)| {
+                let entity_record = self.entity_index.get_mut(entity).unwrap();
+                entity_record.archetype_id = to;
+                entity_record.row = first_row + offset;
+            });
     }
 
-    pub fn create_entity(&mut self) -> EntityCreator {
+    pub fn create_entity(&mut self) -> EntityCreator<'_> {
         EntityCreator {
             world: self,
-            components_set: BTreeSet::new(),
-            archetype: Archetype {
-                columns: vec![],
-                entities: Vec::new(),
-                column_index: HashMap::new(),
-            },
+            components: HashMap::new(),
+            sparse_components: HashMap::new(),
         }
     }
 
     pub fn query(&self) -> QueryCreator {
         QueryCreator {
             component_ids: vec![],
+            relations: vec![],
+            include_disabled: false,
+            entity_order: false,
         }
     }
 }
@@ -345,7 +1397,10 @@ mod tests {
         assert_eq!(entity_record.archetype_id, 0);
         assert_eq!(entity_record.row, 0);
 
-        let archetype_set = world.component_index.get(&TypeId::of::<Health>()).unwrap();
+        let archetype_set = world
+            .component_index
+            .get(&ComponentId::of::<Health>())
+            .unwrap();
         assert_eq!(archetype_set.len(), 1);
 
         let column_id = archetype_set.get(&entity_record.archetype_id).unwrap();
@@ -372,8 +1427,8 @@ mod tests {
         assert_eq!(entity_record.archetype_id, 0);
         assert_eq!(entity_record.row, 0);
 
-        let name_id = TypeId::of::<Name>();
-        let health_id = TypeId::of::<Health>();
+        let name_id = ComponentId::of::<Name>();
+        let health_id = ComponentId::of::<Health>();
         let components_set = BTreeSet::from([name_id, health_id]);
         let archetype_id = world.archetype_index.get(&components_set).unwrap();
 
@@ -383,8 +1438,8 @@ mod tests {
 
         assert_eq!(archetype.columns.len(), 2);
 
-        let healths = &archetype.columns.first().unwrap().components;
-        let names = &archetype.columns.last().unwrap().components;
+        let healths = &archetype.columns[archetype.column_index[&health_id]].components;
+        let names = &archetype.columns[archetype.column_index[&name_id]].components;
 
         assert_eq!(healths.len(), 1);
         assert_eq!(healths.len(), names.len());
@@ -530,4 +1585,468 @@ mod tests {
         let name = result.get::<Name>().unwrap();
         assert_eq!(name.0, "Google");
     }
+
+    #[test]
+    fn insert_and_remove_components() {
+        let mut world = World::default();
+
+        let entity = world.create_entity().with_component(Health(10)).spawn().id;
+
+        world.insert_component(entity, Name(String::from("Carles")));
+        assert_eq!(world.get_component::<Health>(entity).unwrap().0, 10);
+        assert_eq!(world.get_component::<Name>(entity).unwrap().0, "Carles");
+
+        world.insert_component(entity, Health(20));
+        assert_eq!(world.get_component::<Health>(entity).unwrap().0, 20);
+        assert_eq!(world.archetypes.len(), 2);
+
+        let health = world.remove_component::<Health>(entity).unwrap();
+        assert_eq!(health.0, 20);
+        assert!(!world.has_component::<Health>(entity));
+        assert_eq!(world.get_component::<Name>(entity).unwrap().0, "Carles");
+
+        assert!(world.remove_component::<Health>(entity).is_none());
+    }
+
+    #[test]
+    fn despawn_keeps_rows_consistent() {
+        let mut world = World::default();
+
+        let entities = (0..3)
+            .map(|health| {
+                world
+                    .create_entity()
+                    .with_component(Health(health))
+                    .spawn()
+                    .id
+            })
+            .collect::<Vec<_>>();
+
+        assert!(world.despawn(entities[0]));
+        assert!(!world.despawn(entities[0]));
+        assert!(world.get_component::<Health>(entities[0]).is_none());
+
+        assert_eq!(world.get_component::<Health>(entities[1]).unwrap().0, 1);
+        assert_eq!(world.get_component::<Health>(entities[2]).unwrap().0, 2);
+
+        let entity = world.create_entity().with_component(Health(3)).spawn().id;
+        assert!(!entities.contains(&entity));
+        assert_eq!(world.get_component::<Health>(entity).unwrap().0, 3);
+    }
+
+    #[test]
+    fn despawn_drops_components() {
+        use std::sync::Arc;
+
+        let mut world = World::default();
+        let counter = Arc::new(());
+
+        let entity = world
+            .create_entity()
+            .with_component(counter.clone())
+            .spawn()
+            .id;
+        world.insert_component(entity, Health(1));
+        world
+            .create_entity()
+            .with_component(counter.clone())
+            .spawn();
+        assert_eq!(Arc::strong_count(&counter), 3);
+
+        world.despawn(entity);
+        assert_eq!(Arc::strong_count(&counter), 2);
+
+        drop(world);
+        assert_eq!(Arc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn duplicate_components_replace_the_earlier_value() {
+        use std::sync::Arc;
+
+        let mut world = World::default();
+        let counter = Arc::new(());
+
+        let entity_record = world
+            .create_entity()
+            .with_component(Health(1))
+            .with_component(counter.clone())
+            .with_component(Health(2))
+            .with_component(counter.clone())
+            .spawn();
+        assert_eq!(Arc::strong_count(&counter), 2);
+
+        world
+            .create_entity()
+            .with_component(counter.clone())
+            .with_component(Health(3))
+            .spawn();
+
+        assert_eq!(world.archetypes.len(), 1);
+        let archetype = &world.archetypes[entity_record.archetype_id];
+        assert_eq!(archetype.columns.len(), archetype.column_index.len());
+        assert!(archetype
+            .columns
+            .iter()
+            .all(|column| column.components.len() == archetype.entities.len()));
+
+        assert_eq!(
+            world.get_component::<Health>(entity_record.id).unwrap().0,
+            2
+        );
+
+        drop(world);
+        assert_eq!(Arc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn duplicate_sparse_components_replace_the_earlier_value() {
+        let mut world = World::default();
+        world.set_storage_type::<Health>(StorageType::SparseSet);
+
+        let entity = world
+            .create_entity()
+            .with_component(Health(1))
+            .with_component(Health(2))
+            .spawn()
+            .id;
+
+        assert_eq!(world.get_component::<Health>(entity).unwrap().0, 2);
+        assert_eq!(world.sparse_sets[&ComponentId::of::<Health>()].len(), 1);
+    }
+
+    #[test]
+    fn try_with_component_rejects_duplicates() {
+        let mut world = World::default();
+
+        let error = world
+            .create_entity()
+            .try_with_component(Health(1))
+            .and_then(|creator| creator.try_with_component(Name(String::from("Carles"))))
+            .and_then(|creator| creator.try_with_component(Health(2)))
+            .err();
+        assert_eq!(
+            error,
+            Some(EcsError::DuplicateComponent(
+                type_name::<Health>().to_string()
+            ))
+        );
+
+        assert!(world.archetypes.is_empty());
+        assert!(world.entity_index.is_empty());
+    }
+
+    #[test]
+    fn try_spawn_reserves_room_up_front() {
+        let mut world = World::default();
+        world.set_storage_type::<Name>(StorageType::SparseSet);
+
+        let entity_record = world
+            .create_entity()
+            .with_component(Health(1))
+            .with_component(Name(String::from("Carles")))
+            .try_spawn()
+            .unwrap();
+        assert_eq!(
+            world.get_component::<Health>(entity_record.id).unwrap().0,
+            1
+        );
+        assert_eq!(
+            world.get_component::<Name>(entity_record.id).unwrap().0,
+            "Carles"
+        );
+
+        let mut column = column_for::<u64>();
+        assert_eq!(
+            column.try_reserve(usize::MAX),
+            Err(EcsError::AllocationFailed)
+        );
+        assert_eq!(
+            column.try_reserve(isize::MAX as usize / 4),
+            Err(EcsError::AllocationFailed)
+        );
+        assert_eq!(column.capacity(), 0);
+        assert_eq!(column.try_reserve(4), Ok(()));
+        assert!(column.capacity() >= 4);
+    }
+
+    #[test]
+    fn query_single_reports_zero_or_many_matches() {
+        let mut world = World::default();
+        let query = world.query().with_component::<Health>();
+
+        assert_eq!(
+            query.get_single(&world).err(),
+            Some(EcsError::NoMatchingEntity)
+        );
+
+        let entity = world.create_entity().with_component(Health(1)).spawn().id;
+        query.single(&world).get_mut::<Health>().unwrap().0 = 2;
+        assert_eq!(query.single(&world).entity(), entity);
+        assert_eq!(world.get_component::<Health>(entity).unwrap().0, 2);
+
+        world.create_entity().with_component(Health(3)).spawn();
+        assert_eq!(
+            query.get_single(&world).err(),
+            Some(EcsError::MultipleMatchingEntities)
+        );
+    }
+
+    #[test]
+    fn query_get_checks_the_entity_matches() {
+        let mut world = World::default();
+
+        let named = world
+            .create_entity()
+            .with_component(Health(1))
+            .with_component(Name(String::from("Carles")))
+            .spawn()
+            .id;
+        let unnamed = world.create_entity().with_component(Health(2)).spawn().id;
+
+        let query = world
+            .query()
+            .with_component::<Health>()
+            .with_component::<Name>();
+
+        let result = query.get(&world, named).unwrap();
+        assert_eq!(result.get::<Name>().unwrap().0, "Carles");
+        assert_eq!(
+            query.get(&world, unnamed).err(),
+            Some(EcsError::QueryMismatch(unnamed))
+        );
+        assert_eq!(
+            query.get(&world, 100).err(),
+            Some(EcsError::NoSuchEntity(100))
+        );
+
+        query
+            .get_mut(&mut world, named)
+            .unwrap()
+            .get_mut::<Health>()
+            .unwrap()
+            .0 = 10;
+        assert_eq!(world.get_component::<Health>(named).unwrap().0, 10);
+    }
+
+    #[test]
+    fn query_get_many_mut_requires_distinct_entities() {
+        let mut world = World::default();
+
+        let first = world.create_entity().with_component(Health(1)).spawn().id;
+        let second = world.create_entity().with_component(Health(2)).spawn().id;
+        let query = world.query().with_component::<Health>();
+
+        let [mut a, mut b] = query.get_many_mut(&mut world, [first, second]).unwrap();
+        std::mem::swap(
+            a.get_mut::<Health>().unwrap(),
+            b.get_mut::<Health>().unwrap(),
+        );
+        assert_eq!(world.get_component::<Health>(first).unwrap().0, 2);
+
+        assert!(matches!(
+            query.get_many_mut(&mut world, [first, second, first]),
+            Err(EcsError::BorrowConflict(_))
+        ));
+    }
+
+    #[test]
+    fn resources_can_be_borrowed_alongside_queries() {
+        use std::panic::{self, AssertUnwindSafe};
+
+        struct Score(u32);
+
+        let mut world = World::default();
+        world.add_resource(Score(0));
+        (1..=3).for_each(|health| {
+            world.create_entity().with_component(Health(health)).spawn();
+        });
+
+        let mut score = world.borrow_resource_mut::<Score>().unwrap();
+        world
+            .query()
+            .with_component::<Health>()
+            .iter(&world)
+            .for_each(|result| score.0 += result.get::<Health>().unwrap().0);
+        assert!(world.try_get_resource::<Score>().is_err());
+        drop(score);
+
+        let spawned = world.resource_scope(|world, score: &mut Score| {
+            assert!(world.get_resource::<Score>().is_none());
+            score.0 *= 2;
+            world
+                .create_entity()
+                .with_component(Health(score.0))
+                .spawn()
+                .id
+        });
+
+        assert_eq!(world.get_resource::<Score>().unwrap().0, 12);
+        assert_eq!(world.get_component::<Health>(spawned).unwrap().0, 12);
+
+        let panicked = panic::catch_unwind(AssertUnwindSafe(|| {
+            world.resource_scope(|_, score: &mut Score| {
+                score.0 += 1;
+                panic!("scope failed");
+            })
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(world.get_resource::<Score>().unwrap().0, 13);
+    }
+
+    #[test]
+    fn resource_ticks_track_additions_and_changes() {
+        #[derive(Debug, PartialEq, Default)]
+        struct TileSize(u32);
+        #[derive(Debug, PartialEq, Default)]
+        struct GridDivisions(u32);
+
+        let mut world = World::default();
+        let start = world.change_tick();
+
+        assert_eq!(world.insert_resource(TileSize(40)), None);
+        world.init_resource::<TileSize>();
+        world.init_resource::<GridDivisions>();
+        assert_eq!(world.get_resource::<TileSize>().unwrap().0, 40);
+        assert!(world.is_resource_added::<TileSize>(start));
+        assert!(world.is_resource_added::<GridDivisions>(start));
+
+        let last_run = world.change_tick();
+        assert!(!world.is_resource_changed::<TileSize>(last_run));
+
+        let tile_size = world.borrow_resource_mut::<TileSize>().unwrap();
+        drop(tile_size);
+        assert!(!world.is_resource_changed::<TileSize>(last_run));
+
+        world.borrow_resource_mut::<TileSize>().unwrap().0 = 20;
+        assert!(world.is_resource_changed::<TileSize>(last_run));
+        assert!(!world.is_resource_added::<TileSize>(last_run));
+        assert!(!world.is_resource_changed::<GridDivisions>(last_run));
+
+        let last_run = world.change_tick();
+        assert_eq!(world.insert_resource(TileSize(10)), Some(TileSize(20)));
+        assert!(world.is_resource_changed::<TileSize>(last_run));
+        assert!(!world.is_resource_added::<TileSize>(last_run));
+
+        let last_run = world.change_tick();
+        world.resource_scope(|_, divisions: &mut GridDivisions| divisions.0 = 10);
+        assert!(world.is_resource_changed::<GridDivisions>(last_run));
+        assert!(!world.is_resource_added::<GridDivisions>(last_run));
+        assert!(!world.is_resource_changed::<Health>(start));
+    }
+
+    #[test]
+    fn worlds_move_between_threads() {
+        use std::{rc::Rc, thread};
+
+        let mut world = World::default();
+        let entity = world.create_entity().with_component(Health(10)).spawn().id;
+        world.on_insert::<Health>(|_, _, commands| {
+            commands.insert_component(0, Name("hurt".into()))
+        });
+        let handle = Rc::new(());
+        world.insert_non_send_resource(handle.clone());
+
+        let mut world = thread::spawn(move || {
+            world.insert_component(entity, Health(5));
+            world
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(world.get_component::<Name>(entity).unwrap().0, "hurt");
+        assert!(world.get_non_send_resource::<Rc<()>>().is_some());
+        assert!(world.get_non_send_resource_mut::<Rc<()>>().is_some());
+
+        let access = thread::spawn(move || world.get_non_send_resource::<Rc<()>>().is_some());
+        assert!(access.join().is_err());
+        assert_eq!(Rc::strong_count(&handle), 2);
+    }
+
+    mod spawn_properties {
+        use crate::World;
+        use proptest::{collection::vec, prelude::*, sample::subsequence};
+
+        #[derive(Debug, PartialEq, Clone)]
+        struct Small(u8);
+        #[derive(Debug, PartialEq, Clone)]
+        struct Large(u64, u64);
+        #[derive(Debug, PartialEq, Clone)]
+        struct Label(String);
+        #[derive(Debug, PartialEq, Clone)]
+        struct Marker;
+
+        #[derive(Debug, Clone)]
+        enum Component {
+            Small(Small),
+            Large(Large),
+            Label(Label),
+            Marker,
+        }
+
+        fn component(kind: usize) -> BoxedStrategy<Component> {
+            match kind {
+                0 => any::<u8>()
+                    .prop_map(|value| Component::Small(Small(value)))
+                    .boxed(),
+                1 => any::<(u64, u64)>()
+                    .prop_map(|(a, b)| Component::Large(Large(a, b)))
+                    .boxed(),
+                2 => "[a-z]{0,12}"
+                    .prop_map(|label| Component::Label(Label(label)))
+                    .boxed(),
+                _ => Just(Component::Marker).boxed(),
+            }
+        }
+
+        /// A random subset of the component types, in a random order.
+        fn entity() -> impl Strategy<Value = Vec<Component>> {
+            subsequence(vec![0, 1, 2, 3], 0..=4)
+                .prop_shuffle()
+                .prop_flat_map(|kinds| kinds.into_iter().map(component).collect::<Vec<_>>())
+        }
+
+        proptest! {
+            #[test]
+            fn spawned_values_round_trip(entities in vec(entity(), 1..32)) {
+                let mut world = World::default();
+
+                let ids = entities
+                    .iter()
+                    .map(|components| {
+                        components
+                            .iter()
+                            .cloned()
+                            .fold(world.create_entity(), |creator, component| match component {
+                                Component::Small(small) => creator.with_component(small),
+                                Component::Large(large) => creator.with_component(large),
+                                Component::Label(label) => creator.with_component(label),
+                                Component::Marker => creator.with_component(Marker),
+                            })
+                            .spawn()
+                            .id
+                    })
+                    .collect::<Vec<_>>();
+
+                for (id, components) in ids.into_iter().zip(entities) {
+                    let mut small = None;
+                    let mut large = None;
+                    let mut label = None;
+                    let mut marker = None;
+
+                    components.into_iter().for_each(|component| match component {
+                        Component::Small(value) => small = Some(value),
+                        Component::Large(value) => large = Some(value),
+                        Component::Label(value) => label = Some(value),
+                        Component::Marker => marker = Some(Marker),
+                    });
+
+                    prop_assert_eq!(world.get_component::<Small>(id), small.as_ref());
+                    prop_assert_eq!(world.get_component::<Large>(id), large.as_ref());
+                    prop_assert_eq!(world.get_component::<Label>(id), label.as_ref());
+                    prop_assert_eq!(world.get_component::<Marker>(id), marker.as_ref());
+                }
+            }
+        }
+    }
 }
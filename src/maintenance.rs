@@ -0,0 +1,204 @@
+use crate::{ArchetypeId, World};
+
+impl World {
+    /// Frees unused column capacity and retires empty archetypes, whose ids get reused.
+    pub fn shrink_to_fit(&mut self) {
+        self.reserved.clear();
+        self.compact(true);
+    }
+
+    /// Runs a gentler [`World::shrink_to_fit`] every `interval` archetype moves or despawns.
+    pub fn set_maintenance_interval(&mut self, interval: Option<usize>) {
+        self.maintenance_interval = interval;
+        self.structural_changes = 0;
+    }
+
+    /// Called once the outermost operation has finished.
+    pub(crate) fn maintain(&mut self) {
+        let Some(interval) = self.maintenance_interval else {
+            return;
+        };
+
+        if self.structural_changes >= interval {
+            self.structural_changes = 0;
+            self.reserved
+                .retain(|archetype_id| self.archetypes[*archetype_id].entities.is_empty());
+            self.compact(false);
+        }
+    }
+
+    /// Retires empty archetypes and frees capacity, sparing reservations unless `exact`.
+    fn compact(&mut self, exact: bool) {
+        let oversized =
+            |capacity: usize, len: usize| capacity > len && (exact || capacity > len * 2);
+
+        let empty = self
+            .archetypes
+            .iter()
+            .enumerate()
+            .filter(|(archetype_id, archetype)| {
+                archetype.entities.is_empty()
+                    && !self.free_archetypes.contains(archetype_id)
+                    && !self.reserved.contains(archetype_id)
+            })
+            .map(|(archetype_id, _)| archetype_id)
+            .collect::<Vec<_>>();
+
+        empty
+            .into_iter()
+            .for_each(|archetype_id| self.retire_archetype(archetype_id));
+
+        self.archetypes
+            .iter_mut()
+            .enumerate()
+            .filter(|(archetype_id, archetype)| {
+                !self.reserved.contains(archetype_id)
+                    && oversized(archetype.entities.capacity(), archetype.entities.len())
+            })
+            .for_each(|(_, archetype)| {
+                archetype.entities.shrink_to_fit();
+                archetype
+                    .columns
+                    .iter_mut()
+                    .for_each(|column| column.components.shrink_to_fit());
+            });
+
+        self.sparse_sets
+            .values_mut()
+            .filter(|sparse_set| oversized(sparse_set.values().capacity(), sparse_set.len()))
+            .for_each(|sparse_set| sparse_set.shrink_to_fit());
+    }
+
+    /// Removes an empty archetype from every index and frees its columns.
+    fn retire_archetype(&mut self, archetype_id: ArchetypeId) {
+        let archetype = &mut self.archetypes[archetype_id];
+
+        archetype.column_index.keys().for_each(|component_id| {
+            let archetype_map = self.component_index.get_mut(component_id).unwrap();
+            archetype_map.remove(&archetype_id);
+
+            if archetype_map.is_empty() {
+                self.component_index.remove(component_id);
+            }
+        });
+
+        archetype.columns = vec![];
+        archetype.column_index.clear();
+        archetype.entities = vec![];
+
+        self.archetype_index
+            .retain(|_, indexed_id| *indexed_id != archetype_id);
+        self.reserved.remove(&archetype_id);
+        self.free_archetypes.push(archetype_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ComponentId, World};
+
+    #[derive(Debug, PartialEq)]
+    struct Tile(u32);
+    #[derive(Debug, PartialEq)]
+    struct Rail(u32);
+    struct Selected;
+
+    #[test]
+    fn shrink_to_fit_retires_empty_archetypes() {
+        let mut world = World::default();
+
+        let tiles = (0..8)
+            .map(|index| world.create_entity().with_component(Tile(index)).spawn().id)
+            .collect::<Vec<_>>();
+        tiles.iter().skip(2).for_each(|tile| {
+            world.insert_component(*tile, Rail(*tile as u32));
+        });
+        world.insert_component(tiles[0], Selected);
+        world.remove_component::<Selected>(tiles[0]);
+
+        let with_selected = world.archetypes.len() - 1;
+        world.shrink_to_fit();
+
+        assert!(!world
+            .component_index
+            .contains_key(&ComponentId::of::<Selected>()));
+        assert!(!world.component_index[&ComponentId::of::<Tile>()].contains_key(&with_selected));
+        assert!(world
+            .archetype_index
+            .values()
+            .all(|id| *id != with_selected));
+
+        let tiles_only = world.entity_index[&tiles[0]].archetype_id;
+        let tiles_only = &world.archetypes[tiles_only].columns[0].components;
+        assert_eq!(tiles_only.capacity(), 2);
+
+        let rails = world.query().with_component::<Rail>().iter(&world).count();
+        assert_eq!(rails, 6);
+
+        world.insert_component(tiles[1], Selected);
+        assert_eq!(world.entity_index[&tiles[1]].archetype_id, with_selected);
+        assert_eq!(world.get_component::<Tile>(tiles[1]), Some(&Tile(1)));
+        assert_eq!(world.get_component::<Rail>(tiles[7]), Some(&Rail(7)));
+    }
+
+    #[test]
+    fn maintenance_runs_after_enough_structural_changes() {
+        let mut world = World::default();
+        world.set_maintenance_interval(Some(4));
+
+        let tile = world.create_entity().with_component(Tile(0)).spawn().id;
+        world.insert_component(tile, Selected);
+        world.remove_component::<Selected>(tile);
+        world.insert_component(tile, Rail(0));
+        assert!(world.free_archetypes.is_empty());
+
+        world.remove_component::<Rail>(tile);
+        assert_eq!(world.free_archetypes.len(), 2);
+        assert_eq!(world.stats().archetypes.len(), 1);
+
+        world.set_maintenance_interval(None);
+        world.insert_component(tile, Selected);
+        (0..8).for_each(|_| {
+            world.remove_component::<Selected>(tile);
+            world.insert_component(tile, Selected);
+        });
+        assert_eq!(world.free_archetypes.len(), 1);
+    }
+
+    #[test]
+    fn maintenance_keeps_reserved_archetypes() {
+        let mut world = World::default();
+        world.set_maintenance_interval(Some(2));
+        world.reserve_for::<(Tile, Rail)>(16);
+        let reserved = *world.component_index[&ComponentId::of::<Rail>()]
+            .keys()
+            .next()
+            .unwrap();
+
+        (0..4).for_each(|index| {
+            let tile = world.create_entity().with_component(Tile(index)).spawn().id;
+            world.despawn(tile);
+        });
+        assert!(!world.free_archetypes.is_empty());
+        assert!(!world.free_archetypes.contains(&reserved));
+
+        let tiles = (0..16)
+            .map(|index| {
+                world
+                    .create_entity()
+                    .with_component(Tile(index))
+                    .with_component(Rail(index))
+                    .spawn()
+                    .id
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(world.entity_index[&tiles[0]].archetype_id, reserved);
+        let tiles_column = &world.archetypes[reserved].columns[0].components;
+        assert_eq!(tiles_column.capacity(), 16);
+
+        tiles.into_iter().for_each(|tile| {
+            world.despawn(tile);
+        });
+        assert!(world.free_archetypes.contains(&reserved));
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{ArchetypeId, QueryCreator, QueryIter, QueryResult, World};
+use std::vec;
+
+impl QueryCreator {
+    /// Makes [`QueryCreator::iter`] yield matches in ascending entity id order instead of
+    /// storage order, which changes as entities move between archetypes or get swap-removed.
+    pub fn in_entity_order(mut self) -> Self {
+        self.entity_order = true;
+        self
+    }
+
+    /// Iterates over the matches in ascending entity id order.
+    pub fn iter_sorted<'w>(&self, world: &'w World) -> QueryIter<'w> {
+        let mut results = self.iter(world);
+        results.sort_by_entity();
+        results
+    }
+
+    /// Iterates over the matches ordered by `key`. Matches with equal keys come in ascending
+    /// entity id order.
+    pub fn sort_by_key<'w, K: Ord>(
+        &self,
+        world: &'w World,
+        mut key: impl FnMut(&QueryResult<'w>) -> K,
+    ) -> vec::IntoIter<QueryResult<'w>> {
+        let mut results = self.iter_sorted(world).collect::<Vec<_>>();
+        results.sort_by_key(|result| key(result));
+        results.into_iter()
+    }
+}
+
+impl QueryIter<'_> {
+    /// Visits the matches in ascending entity id order. Must be called before iterating.
+    pub(crate) fn sort_by_entity(&mut self) {
+        if self.order.is_some() {
+            return;
+        }
+
+        let world = self.world;
+        let mut rows = self.rows();
+        rows.sort_unstable_by_key(|(archetype_id, row)| {
+            world.archetypes[*archetype_id].entities[*row]
+        });
+
+        self.order = Some(rows.into_iter());
+    }
+
+    /// The `(archetype, row)` position of every match, in storage order.
+    fn rows(&self) -> Vec<(ArchetypeId, usize)> {
+        let world = self.world;
+        self.archetype_ids
+            .iter()
+            .flat_map(|archetype_id| {
+                let archetype = &world.archetypes[*archetype_id];
+
+                (0..archetype.entities.len())
+                    .filter(|row| self.has_sparse_components(archetype, *row))
+                    .map(|row| (*archetype_id, row))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{StorageType, World};
+
+    #[derive(Debug, PartialEq)]
+    struct Train(u32);
+    struct Wagon;
+    struct Derailed;
+
+    /// Spawns trains so that storage order differs from entity id order.
+    fn shuffled_world() -> World {
+        let mut world = World::default();
+        world.set_storage_type::<Derailed>(StorageType::SparseSet);
+
+        (0..6).for_each(|index| {
+            let train = world
+                .create_entity()
+                .with_component(Train(5 - index as u32))
+                .spawn()
+                .id;
+
+            if index % 2 == 0 {
+                world.insert_component(train, Wagon);
+            }
+            if index != 3 {
+                world.insert_component(train, Derailed);
+            }
+        });
+        world.despawn(1);
+
+        world
+    }
+
+    #[test]
+    fn iteration_can_follow_entity_order() {
+        let world = shuffled_world();
+        let query = world.query().with_component::<Train>();
+
+        let entities =
+            |results: crate::QueryIter| results.map(|result| result.entity()).collect::<Vec<_>>();
+        assert_ne!(entities(query.iter(&world)), vec![0, 2, 3, 4, 5]);
+        assert_eq!(entities(query.iter_sorted(&world)), vec![0, 2, 3, 4, 5]);
+
+        let query = query.with_component::<Derailed>().in_entity_order();
+        assert_eq!(entities(query.iter(&world)), vec![0, 2, 4, 5]);
+        assert_eq!(query.get(&world, 4).unwrap().entity(), 4);
+        assert!(query.get(&world, 3).is_err());
+    }
+
+    #[test]
+    fn sort_by_key_breaks_ties_by_entity() {
+        let world = shuffled_world();
+
+        let trains = world
+            .query()
+            .with_component::<Train>()
+            .sort_by_key(&world, |result| result.get::<Train>().unwrap().0 % 2)
+            .map(|result| (result.entity(), result.get::<Train>().unwrap().0))
+            .collect::<Vec<_>>();
+
+        assert_eq!(trains, vec![(3, 2), (5, 0), (0, 5), (2, 3), (4, 1)]);
+    }
+}
@@ -0,0 +1,207 @@
+use crate::{AnyVec, Children, ComponentId, EntityCreator, EntityId, EntityRecord, Parent, World};
+use std::any::{Any, TypeId};
+
+/// Pushes a clone of the element at the given pointer into the vector.
+pub(crate) type CloneFn = unsafe fn(*const u8, &mut AnyVec);
+
+unsafe fn clone_into<T: Clone>(component: *const u8, components: &mut AnyVec) {
+    let component = unsafe { &*(component as *const T) };
+    components.push(component.clone());
+}
+
+type Spawner = Box<dyn Fn(EntityCreator<'_>) -> EntityCreator<'_> + Send + Sync>;
+
+/// A template of component values that can be spawned any number of times.
+///
+/// Every instance gets its own clone of the values. Components added to the returned
+/// [`EntityCreator`] override the template's ones, as adding a component twice replaces it.
+#[derive(Default)]
+pub struct Prefab {
+    components: Vec<Spawner>,
+}
+
+impl Prefab {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_component<T: Any + Send + Sync + Clone>(mut self, component: T) -> Self {
+        self.components.push(Box::new(move |creator| {
+            creator.with_component(component.clone())
+        }));
+        self
+    }
+
+    /// Adds the relation pair `(relation, target)` to every instance.
+    pub fn with_pair<R: Any + Send + Sync + Clone>(
+        mut self,
+        relation: R,
+        target: EntityId,
+    ) -> Self {
+        self.components.push(Box::new(move |creator| {
+            creator.with_pair(relation.clone(), target)
+        }));
+        self
+    }
+}
+
+impl World {
+    /// Allows [`World::clone_entity`] to copy `T` components, and pairs of the relation `T`.
+    pub fn register_clone<T: Any + Clone>(&mut self) {
+        self.clone_fns
+            .insert(TypeId::of::<T>(), clone_into::<T> as CloneFn);
+    }
+
+    /// Spawns a copy of `entity` with a clone of each of its components registered with
+    /// [`World::register_clone`]. Other components are left out, and so are [`Parent`] and
+    /// [`Children`], as the hierarchy can't be shared.
+    pub fn clone_entity(&mut self, entity: EntityId) -> Option<EntityRecord> {
+        let entity_record = *self.entity_index.get(&entity)?;
+        let archetype = &self.archetypes[entity_record.archetype_id];
+
+        let table = archetype
+            .column_index
+            .iter()
+            .map(|(component_id, column_id)| {
+                let components = &archetype.columns[*column_id].components;
+                let component = components.get_raw(entity_record.row).unwrap();
+
+                (*component_id, components, component)
+            });
+
+        let sparse = self
+            .sparse_sets
+            .iter()
+            .filter_map(|(component_id, sparse_set)| {
+                Some((
+                    *component_id,
+                    sparse_set.values(),
+                    sparse_set.get_raw(entity)?,
+                ))
+            });
+
+        let (sparse_components, components): (Vec<_>, Vec<_>) = table
+            .chain(sparse)
+            .filter(|(component_id, _, _)| {
+                *component_id != ComponentId::of::<Parent>()
+                    && *component_id != ComponentId::of::<Children>()
+            })
+            .filter_map(|(component_id, components, component)| {
+                let type_id = match component_id {
+                    ComponentId::Type(type_id) | ComponentId::Pair(type_id, _) => type_id,
+                    ComponentId::Dynamic(_) => return None,
+                };
+                let clone = self.clone_fns.get(&type_id)?;
+
+                let mut clone_column = components.empty_like();
+                unsafe { clone(component, &mut clone_column) };
+
+                Some((component_id, clone_column))
+            })
+            .partition(|(component_id, _)| self.sparse_sets.contains_key(component_id));
+
+        let creator = EntityCreator {
+            world: self,
+            components: components.into_iter().collect(),
+            sparse_components: sparse_components.into_iter().collect(),
+        };
+
+        Some(creator.spawn())
+    }
+
+    /// Starts spawning an instance of `prefab`. More components can be added, or the prefab's
+    /// ones overridden, before calling [`EntityCreator::spawn`].
+    pub fn instantiate(&mut self, prefab: &Prefab) -> EntityCreator<'_> {
+        prefab
+            .components
+            .iter()
+            .fold(self.create_entity(), |creator, spawner| spawner(creator))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StorageType;
+
+    #[derive(Debug, PartialEq, Clone)]
+    struct Train(Vec<u32>);
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    struct GridPosition(u32, u32);
+    #[derive(Debug, PartialEq, Clone)]
+    struct Reserved(u32);
+    #[derive(Debug, PartialEq, Clone)]
+    struct TravelsTo;
+    struct Engine;
+
+    #[test]
+    fn clone_entity_copies_registered_components() {
+        let mut world = World::default();
+        world.set_storage_type::<Reserved>(StorageType::SparseSet);
+        world.register_clone::<Train>();
+        world.register_clone::<GridPosition>();
+        world.register_clone::<Reserved>();
+        world.register_clone::<TravelsTo>();
+
+        let station = world.create_entity().spawn().id;
+        let depot = world.create_entity().spawn().id;
+        let train = world
+            .create_entity()
+            .with_component(Train(vec![1, 2]))
+            .with_component(GridPosition(1, 1))
+            .with_component(Reserved(3))
+            .with_component(Engine)
+            .with_pair(TravelsTo, station)
+            .spawn()
+            .id;
+        world.set_parent(train, depot);
+
+        let copy = world.clone_entity(train).unwrap().id;
+        world.get_component_mut::<Train>(train).unwrap().0.push(3);
+
+        assert_eq!(world.get_component::<Train>(copy), Some(&Train(vec![1, 2])));
+        assert_eq!(
+            world.get_component::<GridPosition>(copy),
+            Some(&GridPosition(1, 1))
+        );
+        assert_eq!(world.get_component::<Reserved>(copy), Some(&Reserved(3)));
+        assert!(world.has_pair::<TravelsTo>(copy, station));
+        assert!(!world.has_component::<Engine>(copy));
+        assert_eq!(world.parent(copy), None);
+        assert_eq!(world.children(depot), &[train]);
+
+        assert!(world.clone_entity(100).is_none());
+    }
+
+    #[test]
+    fn prefabs_spawn_with_overrides() {
+        let mut world = World::default();
+
+        let prefab = Prefab::new()
+            .with_component(Train(vec![4, 0, 5]))
+            .with_component(GridPosition(0, 0));
+
+        let trains = (1..=3)
+            .map(|offset| {
+                world
+                    .instantiate(&prefab)
+                    .with_component(GridPosition(offset, offset))
+                    .spawn()
+            })
+            .collect::<Vec<_>>();
+
+        assert!(trains
+            .iter()
+            .all(|train| train.archetype_id == trains[0].archetype_id));
+        trains.iter().zip(1..).for_each(|(train, offset)| {
+            assert_eq!(
+                world.get_component::<GridPosition>(train.id),
+                Some(&GridPosition(offset, offset))
+            );
+            assert_eq!(
+                world.get_component::<Train>(train.id),
+                Some(&Train(vec![4, 0, 5]))
+            );
+        });
+    }
+}
@@ -0,0 +1,213 @@
+use crate::{ComponentId, EntityId, Lifecycle, World};
+use std::any::{Any, TypeId};
+
+impl World {
+    /// Adds the relation pair `(relation, target)` to `entity`, replacing the value of an
+    /// existing pair with the same target. Does nothing if either entity doesn't exist.
+    ///
+    /// The pair is removed again when `target` is despawned.
+    pub fn insert_pair<R: Any + Send + Sync>(
+        &mut self,
+        entity: EntityId,
+        relation: R,
+        target: EntityId,
+    ) {
+        if !self.contains(target) {
+            return;
+        }
+
+        self.insert_component_by_id(entity, ComponentId::pair::<R>(target), relation);
+    }
+
+    pub fn remove_pair<R: Any>(&mut self, entity: EntityId, target: EntityId) -> Option<R> {
+        self.remove_component_by_id(entity, ComponentId::pair::<R>(target))
+    }
+
+    pub fn has_pair<R: Any>(&self, entity: EntityId, target: EntityId) -> bool {
+        self.has_component_id(entity, ComponentId::pair::<R>(target))
+    }
+
+    pub fn get_pair<R: Any>(&self, entity: EntityId, target: EntityId) -> Option<&R> {
+        self.get_component_by_id(entity, ComponentId::pair::<R>(target))
+    }
+
+    pub fn get_pair_mut<R: Any>(&mut self, entity: EntityId, target: EntityId) -> Option<&mut R> {
+        self.get_component_mut_by_id(entity, ComponentId::pair::<R>(target))
+    }
+
+    /// The targets of every `R` pair on `entity`, in ascending order.
+    pub fn targets<R: Any>(&self, entity: EntityId) -> Vec<EntityId> {
+        let Some(entity_record) = self.entity_index.get(&entity) else {
+            return vec![];
+        };
+
+        let relation = TypeId::of::<R>();
+
+        let mut targets = self.archetypes[entity_record.archetype_id]
+            .column_index
+            .keys()
+            .filter_map(|component_id| match component_id {
+                ComponentId::Pair(pair_relation, target) if *pair_relation == relation => {
+                    Some(*target)
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        targets.sort_unstable();
+        targets
+    }
+
+    /// Drops every pair, of any relation, whose target is `target`.
+    pub(crate) fn remove_pairs_targeting(&mut self, target: EntityId) {
+        let pairs = self
+            .component_index
+            .keys()
+            .filter(|component_id| {
+                matches!(component_id, ComponentId::Pair(_, pair_target) if *pair_target == target)
+            })
+            .copied()
+            .collect::<Vec<_>>();
+
+        pairs.into_iter().for_each(|component_id| {
+            let entities = self.component_index[&component_id]
+                .keys()
+                .flat_map(|archetype_id| self.archetypes[*archetype_id].entities.iter().copied())
+                .collect::<Vec<_>>();
+
+            entities.into_iter().for_each(|entity| {
+                self.trigger(entity, component_id, Lifecycle::Remove);
+                self.remove_column(entity, component_id, true);
+            });
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TravelsTo;
+    #[derive(Debug, PartialEq)]
+    struct OccupiedBy(u32);
+    struct Train;
+    struct Station;
+
+    #[test]
+    fn insert_and_remove_pairs() {
+        let mut world = World::default();
+
+        let station = world.create_entity().with_component(Station).spawn().id;
+        let depot = world.create_entity().with_component(Station).spawn().id;
+        let train = world.create_entity().with_component(Train).spawn().id;
+
+        world.insert_pair(station, OccupiedBy(1), train);
+        world.insert_pair(train, TravelsTo, station);
+        world.insert_pair(train, TravelsTo, depot);
+
+        assert!(world.has_pair::<TravelsTo>(train, station));
+        assert!(!world.has_pair::<TravelsTo>(station, train));
+        assert_eq!(world.targets::<TravelsTo>(train), vec![station, depot]);
+        assert_eq!(
+            world.get_pair::<OccupiedBy>(station, train),
+            Some(&OccupiedBy(1))
+        );
+
+        world.get_pair_mut::<OccupiedBy>(station, train).unwrap().0 = 2;
+        assert_eq!(
+            world.remove_pair::<OccupiedBy>(station, train),
+            Some(OccupiedBy(2))
+        );
+        assert!(!world.has_pair::<OccupiedBy>(station, train));
+
+        assert!(world.remove_pair::<TravelsTo>(train, station).is_some());
+        assert_eq!(world.targets::<TravelsTo>(train), vec![depot]);
+    }
+
+    #[test]
+    fn pairs_are_part_of_the_archetype() {
+        let mut world = World::default();
+
+        let station = world.create_entity().with_component(Station).spawn().id;
+        let depot = world.create_entity().with_component(Station).spawn().id;
+
+        let first = world
+            .create_entity()
+            .with_component(Train)
+            .with_pair(TravelsTo, station)
+            .spawn();
+        let second = world
+            .create_entity()
+            .with_component(Train)
+            .with_pair(TravelsTo, depot)
+            .spawn();
+
+        assert_ne!(first.archetype_id, second.archetype_id);
+    }
+
+    #[test]
+    fn query_specific_and_any_target() {
+        let mut world = World::default();
+
+        let station = world.create_entity().with_component(Station).spawn().id;
+        let depot = world.create_entity().with_component(Station).spawn().id;
+
+        let trains = (0..3)
+            .map(|_| world.create_entity().with_component(Train).spawn().id)
+            .collect::<Vec<_>>();
+
+        world.insert_pair(trains[0], TravelsTo, station);
+        world.insert_pair(trains[1], TravelsTo, depot);
+        world.insert_pair(trains[1], TravelsTo, station);
+
+        let to_station = world
+            .query()
+            .with_component::<Train>()
+            .with_pair::<TravelsTo>(station)
+            .iter(&world)
+            .count();
+        assert_eq!(to_station, 2);
+
+        let mut targets = world
+            .query()
+            .with_any_pair::<TravelsTo>()
+            .iter(&world)
+            .map(|result| result.targets::<TravelsTo>())
+            .collect::<Vec<_>>();
+        targets.sort();
+
+        assert_eq!(targets, vec![vec![station], vec![station, depot]]);
+    }
+
+    #[test]
+    fn despawning_the_target_removes_its_pairs() {
+        let mut world = World::default();
+
+        let station = world.create_entity().with_component(Station).spawn().id;
+        let depot = world.create_entity().with_component(Station).spawn().id;
+        let train = world.create_entity().with_component(Train).spawn().id;
+
+        world.insert_pair(train, TravelsTo, station);
+        world.insert_pair(train, TravelsTo, depot);
+        world.insert_pair(station, OccupiedBy(1), train);
+
+        world.despawn(station);
+
+        assert!(world.contains(train));
+        assert!(world.has_component::<Train>(train));
+        assert_eq!(world.targets::<TravelsTo>(train), vec![depot]);
+
+        world.despawn(train);
+        assert!(!world.has_pair::<OccupiedBy>(depot, train));
+
+        let travelling = world
+            .query()
+            .with_any_pair::<TravelsTo>()
+            .iter(&world)
+            .count();
+        assert_eq!(travelling, 0);
+
+        world.insert_pair(depot, TravelsTo, train);
+        assert_eq!(world.targets::<TravelsTo>(depot), vec![]);
+    }
+}
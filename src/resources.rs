@@ -1,34 +1,288 @@
+use crate::EcsError;
 use std::{
-    any::{Any, TypeId},
+    any::{type_name, Any, TypeId},
+    cell::{Cell, Ref, RefCell, RefMut},
     collections::HashMap,
+    fmt, mem,
+    ops::{Deref, DerefMut},
+    thread::{self, ThreadId},
 };
 
+/// Shared access to a resource. The resource can't be borrowed mutably until this is dropped.
+pub struct Res<'a, T> {
+    value: Ref<'a, T>,
+}
+
+impl<T> Deref for Res<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Res<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+/// Exclusive access to a resource. The resource can't be borrowed again until this is dropped.
+/// Mutating through it marks the resource as changed.
+pub struct ResMut<'a, T> {
+    value: RefMut<'a, T>,
+    changed: &'a Cell<Tick>,
+    tick: &'a Cell<Tick>,
+}
+
+impl<T> Deref for ResMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for ResMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.changed.set(next_tick(self.tick));
+        &mut self.value
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ResMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+/// A point in the history of a [`Resources`] store. Every insertion or mutable access to a
+/// resource happens at a new, greater tick.
+pub type Tick = u64;
+
+fn next_tick(tick: &Cell<Tick>) -> Tick {
+    tick.set(tick.get() + 1);
+    tick.get()
+}
+
+#[derive(Debug)]
+struct Resource {
+    value: RefCell<Box<dyn Any + Send + Sync>>,
+    added: Tick,
+    changed: Cell<Tick>,
+}
+
+/// Every resource sits behind its own borrow flag, so different resources can be borrowed
+/// mutably at the same time through a shared reference. Resources have to be `Send + Sync`, see
+/// [`NonSendResources`] for the others.
 #[derive(Debug, Default)]
 pub struct Resources {
-    resources: HashMap<TypeId, Box<dyn Any>>,
+    resources: HashMap<TypeId, Resource>,
+    tick: Cell<Tick>,
 }
 
 impl Resources {
-    pub fn add<T: Any>(&mut self, resource: T) {
-        let type_id = resource.type_id();
-        self.resources.insert(type_id, Box::new(resource));
+    pub fn add<T: Any + Send + Sync>(&mut self, resource: T) {
+        self.insert(resource);
     }
 
-    pub fn get_ref<T: Any>(&self) -> Option<&T> {
-        self.resources
-            .get(&TypeId::of::<T>())
-            .and_then(|any| any.downcast_ref())
+    /// Adds `resource`, returning the value it replaced. A replaced resource keeps its added tick.
+    pub fn insert<T: Any + Send + Sync>(&mut self, resource: T) -> Option<T> {
+        let added = self.added_tick::<T>();
+        let replaced = self.take::<T>();
+        self.restore(resource, added);
+        replaced
+    }
+
+    /// Adds the default `T` unless there already is a `T` resource.
+    pub fn init<T: Any + Send + Sync + Default>(&mut self) {
+        if !self.resources.contains_key(&TypeId::of::<T>()) {
+            self.add(T::default());
+        }
+    }
+
+    /// Panics if the resource is borrowed mutably, see [`Resources::try_borrow`].
+    pub fn get_ref<T: Any>(&self) -> Option<Res<'_, T>> {
+        match self.try_borrow() {
+            Ok(resource) => Some(resource),
+            Err(EcsError::MissingResource(_)) => None,
+            Err(error) => panic!("{error}"),
+        }
     }
 
+    /// Marks the resource as changed.
     pub fn get_mut<T: Any>(&mut self) -> Option<&mut T> {
-        self.resources
-            .get_mut(&TypeId::of::<T>())
-            .and_then(|any| any.downcast_mut())
+        let resource = self.resources.get_mut(&TypeId::of::<T>())?;
+        resource.changed.set(next_tick(&self.tick));
+        resource.value.get_mut().downcast_mut()
+    }
+
+    /// Panics if the resource is already borrowed, see [`Resources::try_borrow_mut`].
+    pub fn borrow_mut<T: Any>(&self) -> Option<ResMut<'_, T>> {
+        match self.try_borrow_mut() {
+            Ok(resource) => Some(resource),
+            Err(EcsError::MissingResource(_)) => None,
+            Err(error) => panic!("{error}"),
+        }
+    }
+
+    pub fn try_borrow<T: Any>(&self) -> Result<Res<'_, T>, EcsError> {
+        let resource = self
+            .resource::<T>()?
+            .value
+            .try_borrow()
+            .map_err(|_| EcsError::BorrowConflict(type_name::<T>().to_string()))?;
+
+        Ok(Res {
+            value: Ref::map(resource, |any| any.downcast_ref().unwrap()),
+        })
+    }
+
+    pub fn try_borrow_mut<T: Any>(&self) -> Result<ResMut<'_, T>, EcsError> {
+        let resource = self.resource::<T>()?;
+        let value = resource
+            .value
+            .try_borrow_mut()
+            .map_err(|_| EcsError::BorrowConflict(type_name::<T>().to_string()))?;
+
+        Ok(ResMut {
+            value: RefMut::map(value, |any| any.downcast_mut().unwrap()),
+            changed: &resource.changed,
+            tick: &self.tick,
+        })
+    }
+
+    /// Removes the resource and hands it back.
+    pub fn take<T: Any>(&mut self) -> Option<T> {
+        let resource = self.resources.remove(&TypeId::of::<T>())?;
+        resource
+            .value
+            .into_inner()
+            .downcast()
+            .ok()
+            .map(|resource| *resource)
+    }
+
+    /// Puts back a resource taken out with [`Resources::take`], keeping its `added` tick, or
+    /// marking it as just added if there's none.
+    pub(crate) fn restore<T: Any + Send + Sync>(&mut self, resource: T, added: Option<Tick>) {
+        let tick = next_tick(&self.tick);
+        self.resources.insert(
+            TypeId::of::<T>(),
+            Resource {
+                value: RefCell::new(Box::new(resource)),
+                added: added.unwrap_or(tick),
+                changed: Cell::new(tick),
+            },
+        );
     }
 
     pub fn delete<T: Any>(&mut self) {
         self.resources.remove(&TypeId::of::<T>());
     }
+
+    /// The latest tick handed out. Resources added or changed later are newer than it.
+    pub fn tick(&self) -> Tick {
+        self.tick.get()
+    }
+
+    pub fn added_tick<T: Any>(&self) -> Option<Tick> {
+        Some(self.resource::<T>().ok()?.added)
+    }
+
+    /// When the resource was last added, replaced or mutably accessed.
+    pub fn changed_tick<T: Any>(&self) -> Option<Tick> {
+        Some(self.resource::<T>().ok()?.changed.get())
+    }
+
+    fn resource<T: Any>(&self) -> Result<&Resource, EcsError> {
+        self.resources
+            .get(&TypeId::of::<T>())
+            .ok_or_else(EcsError::missing_resource::<T>)
+    }
+
+    pub fn len(&self) -> usize {
+        self.resources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+}
+
+/// Resources that can't leave the thread that created the world, such as handles into a
+/// windowing or rendering library. Reaching them from another thread panics.
+///
+/// If the world is dropped on another thread, they are leaked rather than dropped there.
+#[derive(Debug)]
+pub struct NonSendResources {
+    resources: HashMap<TypeId, Box<dyn Any>>,
+    owner: ThreadId,
+}
+
+impl Default for NonSendResources {
+    fn default() -> Self {
+        Self {
+            resources: HashMap::new(),
+            owner: thread::current().id(),
+        }
+    }
+}
+
+impl NonSendResources {
+    pub fn insert<T: Any>(&mut self, resource: T) -> Option<T> {
+        self.check_thread::<T>();
+        let replaced = self
+            .resources
+            .insert(TypeId::of::<T>(), Box::new(resource))?;
+        replaced.downcast().ok().map(|resource| *resource)
+    }
+
+    pub fn get_ref<T: Any>(&self) -> Option<&T> {
+        self.check_thread::<T>();
+        self.resources.get(&TypeId::of::<T>())?.downcast_ref()
+    }
+
+    pub fn get_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.check_thread::<T>();
+        self.resources.get_mut(&TypeId::of::<T>())?.downcast_mut()
+    }
+
+    pub fn take<T: Any>(&mut self) -> Option<T> {
+        self.check_thread::<T>();
+        let resource = self.resources.remove(&TypeId::of::<T>())?;
+        resource.downcast().ok().map(|resource| *resource)
+    }
+
+    /// Whether the current thread is the one the resources belong to.
+    pub fn is_owner(&self) -> bool {
+        thread::current().id() == self.owner
+    }
+
+    pub fn len(&self) -> usize {
+        self.resources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+
+    fn check_thread<T: Any>(&self) {
+        if !self.is_owner() {
+            panic!(
+                "the non-Send resource {} was accessed from another thread than the world's",
+                type_name::<T>()
+            );
+        }
+    }
+}
+
+impl Drop for NonSendResources {
+    fn drop(&mut self) {
+        if !self.is_owner() {
+            mem::forget(mem::take(&mut self.resources));
+        }
+    }
 }
 
 #[cfg(test)]
@@ -47,6 +301,7 @@ mod tests {
         resources.add(resource);
 
         let extracted = resources.resources.get(&resource_type_id).unwrap();
+        let extracted = extracted.value.borrow();
         let extracted = extracted.downcast_ref::<WorldWidth>().unwrap();
 
         assert_eq!(extracted.0, 150);
@@ -89,11 +344,34 @@ mod tests {
         let resource = WorldWidth(150);
         resources.add(resource);
 
-        let extracted = resources.get_ref::<WorldWidth>();
-        assert!(extracted.is_some());
+        assert!(resources.get_ref::<WorldWidth>().is_some());
 
         resources.delete::<WorldWidth>();
         let extracted = resources.get_ref::<WorldWidth>();
         assert!(extracted.is_none());
     }
+
+    #[test]
+    fn borrows_are_tracked_per_resource() {
+        let mut resources = Resources::default();
+        resources.add(WorldWidth(150));
+        resources.add(String::from("grid"));
+
+        let width = resources.try_borrow::<WorldWidth>().unwrap();
+        let mut name = resources.borrow_mut::<String>().unwrap();
+        name.push_str("-1");
+
+        assert!(matches!(
+            resources.try_borrow_mut::<WorldWidth>(),
+            Err(EcsError::BorrowConflict(_))
+        ));
+        assert!(resources.try_borrow::<String>().is_err());
+        assert_eq!(resources.get_ref::<WorldWidth>().unwrap().0, width.0);
+
+        drop((width, name));
+        resources.borrow_mut::<WorldWidth>().unwrap().0 = 300;
+        assert_eq!(resources.get_ref::<WorldWidth>().unwrap().0, 300);
+        assert_eq!(resources.take::<String>().as_deref(), Some("grid-1"));
+        assert!(resources.get_ref::<String>().is_none());
+    }
 }
@@ -0,0 +1,257 @@
+use crate::{Archetype, ComponentId, EntityCreator, EntityId, QueryCreator, QueryResult, World};
+use std::{
+    any::{Any, TypeId},
+    marker::PhantomData,
+};
+
+/// Holds a shared value on the entity standing for it, so queries for `T` don't match it.
+#[derive(Hash)]
+pub(crate) struct SharedValue<T>(T);
+
+/// The relation from an entity to the shared `T` value it references.
+struct SharedRef<T>(PhantomData<fn() -> T>);
+
+impl<T> SharedRef<T> {
+    fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl World {
+    /// The entity standing for the shared `value`, stored unless an equal one already is.
+    pub fn add_shared<T: Any + Send + Sync + PartialEq>(&mut self, value: T) -> EntityId {
+        let mut values = self
+            .shared_values
+            .remove(&TypeId::of::<T>())
+            .unwrap_or_default();
+
+        // Despawned values are pruned here rather than on every despawn.
+        values.retain(|shared| self.has_component::<SharedValue<T>>(*shared));
+
+        let existing = values
+            .iter()
+            .copied()
+            .find(|shared| self.shared_value(*shared) == Some(&value));
+
+        let shared = existing.unwrap_or_else(|| {
+            let shared = self
+                .create_entity()
+                .with_component(SharedValue(value))
+                .spawn()
+                .id;
+            values.push(shared);
+            shared
+        });
+
+        self.shared_values.insert(TypeId::of::<T>(), values);
+        shared
+    }
+
+    /// Makes `entity` reference the shared `value`, see [`World::add_shared`].
+    pub fn insert_shared<T: Any + Send + Sync + PartialEq>(
+        &mut self,
+        entity: EntityId,
+        value: T,
+    ) -> EntityId {
+        let shared = self.add_shared(value);
+        self.set_shared::<T>(entity, shared);
+        shared
+    }
+
+    /// Makes `entity` reference the shared `T` value `shared` stands for, if it holds one.
+    pub fn set_shared<T: Any>(&mut self, entity: EntityId, shared: EntityId) {
+        let holds_value = self.has_component::<SharedValue<T>>(shared);
+        if !holds_value || self.shared::<T>(entity) == Some(shared) {
+            return;
+        }
+
+        self.deferred(|world| {
+            world.remove_shared::<T>(entity);
+            world.insert_pair(entity, SharedRef::<T>::new(), shared);
+        });
+    }
+
+    /// Stops `entity` referencing its shared `T` value, returning the entity standing for it.
+    pub fn remove_shared<T: Any>(&mut self, entity: EntityId) -> Option<EntityId> {
+        let shared = self.shared::<T>(entity)?;
+        self.remove_pair::<SharedRef<T>>(entity, shared);
+        Some(shared)
+    }
+
+    /// The entity standing for the shared `T` value `entity` references.
+    pub fn shared<T: Any>(&self, entity: EntityId) -> Option<EntityId> {
+        let entity_record = self.entity_index.get(&entity)?;
+        shared_target::<T>(&self.archetypes[entity_record.archetype_id])
+    }
+
+    /// The shared `T` value `entity` references.
+    pub fn get_shared<T: Any>(&self, entity: EntityId) -> Option<&T> {
+        self.shared_value(self.shared::<T>(entity)?)
+    }
+
+    /// The shared `T` value `shared` stands for.
+    pub fn shared_value<T: Any>(&self, shared: EntityId) -> Option<&T> {
+        Some(&self.get_component::<SharedValue<T>>(shared)?.0)
+    }
+
+    /// The shared `T` value `shared` stands for, which every entity referencing it sees change.
+    pub fn shared_value_mut<T: Any>(&mut self, shared: EntityId) -> Option<&mut T> {
+        Some(&mut self.get_component_mut::<SharedValue<T>>(shared)?.0)
+    }
+
+    /// The shared `T` value referenced by every entity of `archetype`.
+    pub(crate) fn archetype_shared<T: Any>(&self, archetype: &Archetype) -> Option<&T> {
+        self.shared_value(shared_target::<T>(archetype)?)
+    }
+}
+
+/// The target of the `SharedRef<T>` pair of `archetype`, of which there is at most one.
+fn shared_target<T: Any>(archetype: &Archetype) -> Option<EntityId> {
+    let relation = TypeId::of::<SharedRef<T>>();
+
+    archetype
+        .column_index
+        .keys()
+        .find_map(|component_id| match component_id {
+            ComponentId::Pair(pair_relation, target) if *pair_relation == relation => Some(*target),
+            _ => None,
+        })
+}
+
+impl EntityCreator<'_> {
+    /// Makes the entity reference the shared `T` value `shared` stands for, if it holds one.
+    pub fn with_shared<T: Any>(mut self, shared: EntityId) -> Self {
+        if !self.world.has_component::<SharedValue<T>>(shared) {
+            return self;
+        }
+
+        let relation = TypeId::of::<SharedRef<T>>();
+        self.components
+            .retain(|component_id, _| !component_id.is_pair_of(relation));
+
+        self.with_pair(SharedRef::<T>::new(), shared)
+    }
+}
+
+impl QueryCreator {
+    /// Matches entities referencing the shared `T` value `shared` stands for.
+    pub fn with_shared<T: Any>(self, shared: EntityId) -> Self {
+        self.with_pair::<SharedRef<T>>(shared)
+    }
+
+    /// Matches entities referencing any shared `T` value.
+    pub fn with_any_shared<T: Any>(self) -> Self {
+        self.with_any_pair::<SharedRef<T>>()
+    }
+}
+
+impl QueryResult<'_> {
+    /// The shared `T` value the entity references.
+    pub fn get_shared<T: Any>(&self) -> Option<&T> {
+        self.world.get_shared(self.entity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::World;
+
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    struct LineColor(u8, u8, u8);
+    struct Rail(u32);
+
+    #[test]
+    fn shared_values_are_stored_once() {
+        let mut world = World::default();
+
+        let rails = (0..4)
+            .map(|index| world.create_entity().with_component(Rail(index)).spawn().id)
+            .collect::<Vec<_>>();
+        let black = world.insert_shared(rails[0], LineColor(0, 0, 0));
+        let red = world.insert_shared(rails[1], LineColor(255, 0, 0));
+        assert_eq!(world.insert_shared(rails[2], LineColor(0, 0, 0)), black);
+        assert_ne!(black, red);
+
+        let station = world
+            .create_entity()
+            .with_shared::<LineColor>(red)
+            .with_shared::<LineColor>(black)
+            .spawn()
+            .id;
+        assert_eq!(world.get_shared(station), Some(&LineColor(0, 0, 0)));
+        assert_eq!(
+            world
+                .query()
+                .with_component::<LineColor>()
+                .iter(&world)
+                .count(),
+            0
+        );
+
+        *world.shared_value_mut::<LineColor>(black).unwrap() = LineColor(0, 0, 255);
+        assert_eq!(world.get_shared(rails[0]), Some(&LineColor(0, 0, 255)));
+        assert_eq!(world.get_shared(rails[2]), Some(&LineColor(0, 0, 255)));
+        assert_eq!(world.get_shared(rails[1]), Some(&LineColor(255, 0, 0)));
+        assert_eq!(world.get_shared::<LineColor>(rails[3]), None);
+
+        world.set_shared::<LineColor>(rails[2], red);
+        assert_eq!(world.shared::<LineColor>(rails[2]), Some(red));
+        assert_eq!(world.remove_shared::<LineColor>(rails[0]), Some(black));
+        assert_eq!(world.get_shared::<LineColor>(rails[0]), None);
+
+        world.despawn(red);
+        assert_eq!(world.get_shared::<LineColor>(rails[1]), None);
+        assert_eq!(world.get_shared(station), Some(&LineColor(0, 0, 255)));
+        assert_eq!(world.add_shared(LineColor(0, 0, 255)), black);
+        let new_red = world.add_shared(LineColor(255, 0, 0));
+        assert_eq!(world.shared_value(new_red), Some(&LineColor(255, 0, 0)));
+    }
+
+    #[test]
+    fn queries_group_by_shared_value() {
+        let mut world = World::default();
+        let black = world.add_shared(LineColor(0, 0, 0));
+        let red = world.add_shared(LineColor(255, 0, 0));
+
+        (0..6).for_each(|index| {
+            let line = if index % 2 == 0 { black } else { red };
+            world
+                .create_entity()
+                .with_component(Rail(index))
+                .with_shared::<LineColor>(line)
+                .spawn();
+        });
+
+        let mut chunks = world
+            .query()
+            .with_component::<Rail>()
+            .iter_chunks(&world)
+            .map(|chunk| {
+                let rails = chunk.get::<Rail>().unwrap().iter().map(|rail| rail.0);
+                (*chunk.get_shared::<LineColor>().unwrap(), rails.collect())
+            })
+            .collect::<Vec<(_, Vec<_>)>>();
+        chunks.sort_by_key(|(color, _)| color.0);
+        assert_eq!(
+            chunks,
+            vec![
+                (LineColor(0, 0, 0), vec![0, 2, 4]),
+                (LineColor(255, 0, 0), vec![1, 3, 5]),
+            ]
+        );
+
+        let query = world.query().with_shared::<LineColor>(red);
+        assert!(query
+            .iter(&world)
+            .all(|result| result.get_shared() == Some(&LineColor(255, 0, 0))));
+        assert_eq!(query.iter(&world).count(), 3);
+        assert_eq!(
+            world
+                .query()
+                .with_any_shared::<LineColor>()
+                .iter(&world)
+                .count(),
+            6
+        );
+    }
+}
@@ -0,0 +1,293 @@
+use crate::{AnyVec, EcsError, EntityId};
+
+/// Where the values of a component type live.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum StorageType {
+    /// Stored in the archetype tables. Fast to iterate, but adding or removing the component
+    /// moves the entity to another archetype.
+    #[default]
+    Table,
+    /// Stored in a sparse set outside the archetype tables. Adding or removing the component
+    /// never moves the entity, which suits components that are toggled often.
+    SparseSet,
+}
+
+/// Maps entities to the densely packed values of a single component type.
+#[derive(Debug)]
+pub struct SparseSet {
+    dense: AnyVec,
+    entities: Vec<EntityId>,
+    sparse: Vec<Option<usize>>,
+}
+
+impl SparseSet {
+    pub fn new(dense: AnyVec) -> Self {
+        Self {
+            dense,
+            entities: Vec::new(),
+            sparse: Vec::new(),
+        }
+    }
+
+    pub fn contains(&self, entity: EntityId) -> bool {
+        self.dense_index(entity).is_some()
+    }
+
+    pub fn get_raw(&self, entity: EntityId) -> Option<*const u8> {
+        self.dense.get_raw(self.dense_index(entity)?)
+    }
+
+    pub fn get<T>(&self, entity: EntityId) -> Option<&T> {
+        self.dense.get(self.dense_index(entity)?)
+    }
+
+    pub fn get_mut<T>(&mut self, entity: EntityId) -> Option<&mut T> {
+        let index = self.dense_index(entity)?;
+        self.dense.get_mut(index)
+    }
+
+    /// Stores `value` for `entity`, dropping the value it replaces.
+    pub fn insert<T>(&mut self, entity: EntityId, value: T) {
+        if let Some(current) = self.get_mut::<T>(entity) {
+            *current = value;
+            return;
+        }
+
+        if self.sparse.len() <= entity {
+            self.sparse.resize(entity + 1, None);
+        }
+
+        self.sparse[entity] = Some(self.entities.len());
+        self.entities.push(entity);
+        self.dense.push(value);
+    }
+
+    /// # Safety
+    /// `bytes` must point to a valid value of this set's component, which is moved into the set
+    /// and must not be used or dropped by the caller afterwards.
+    pub unsafe fn insert_raw(&mut self, entity: EntityId, bytes: *const u8) {
+        if let Some(index) = self.dense_index(entity) {
+            unsafe { self.dense.replace_raw(index, bytes) };
+            return;
+        }
+
+        if self.sparse.len() <= entity {
+            self.sparse.resize(entity + 1, None);
+        }
+
+        self.sparse[entity] = Some(self.entities.len());
+        self.entities.push(entity);
+        unsafe { self.dense.push_raw(bytes) };
+    }
+
+    pub fn remove<T>(&mut self, entity: EntityId) -> Option<T> {
+        let index = self.dense_index(entity)?;
+        let value = self.dense.swap_remove(index);
+        self.remove_entity(index);
+
+        value
+    }
+
+    /// Drops the value stored for `entity`. Returns false if there was none.
+    pub fn remove_and_drop(&mut self, entity: EntityId) -> bool {
+        let Some(index) = self.dense_index(entity) else {
+            return false;
+        };
+
+        self.dense.swap_remove_drop(index);
+        self.remove_entity(index);
+
+        true
+    }
+
+    /// Removes the value stored for `entity` without dropping it. Returns false if there was
+    /// none.
+    ///
+    /// # Safety
+    /// The value must have been moved out (e.g. with [`AnyVec::push_raw`] into another vector)
+    /// or otherwise be safe to forget.
+    pub unsafe fn remove_and_forget(&mut self, entity: EntityId) -> bool {
+        let Some(index) = self.dense_index(entity) else {
+            return false;
+        };
+
+        unsafe { self.dense.swap_remove_forget(index) };
+        self.remove_entity(index);
+
+        true
+    }
+
+    /// The packed values, in the same order as [`SparseSet::entities`].
+    pub fn values(&self) -> &AnyVec {
+        &self.dense
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        self.dense.reserve(additional);
+        self.entities.reserve(additional);
+    }
+
+    /// Makes room to insert a value for `entity`, reporting running out of memory instead of
+    /// aborting.
+    pub fn try_reserve_for(&mut self, entity: EntityId) -> Result<(), EcsError> {
+        self.dense.try_reserve(1)?;
+        self.entities
+            .try_reserve(1)
+            .map_err(|_| EcsError::AllocationFailed)?;
+
+        let additional = (entity + 1).saturating_sub(self.sparse.len());
+        self.sparse
+            .try_reserve(additional)
+            .map_err(|_| EcsError::AllocationFailed)
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        self.dense.shrink_to_fit();
+        self.entities.shrink_to_fit();
+
+        let len = self
+            .sparse
+            .iter()
+            .rposition(Option::is_some)
+            .map_or(0, |last| last + 1);
+        self.sparse.truncate(len);
+        self.sparse.shrink_to_fit();
+    }
+
+    pub fn entities(&self) -> &[EntityId] {
+        &self.entities
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    fn dense_index(&self, entity: EntityId) -> Option<usize> {
+        self.sparse.get(entity).copied().flatten()
+    }
+
+    /// Mirrors a swap remove of the dense value at `index` in the entity bookkeeping.
+    fn remove_entity(&mut self, index: usize) {
+        let entity = self.entities.swap_remove(index);
+        self.sparse[entity] = None;
+
+        if let Some(moved) = self.entities.get(index) {
+            self.sparse[*moved] = Some(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::World;
+
+    struct Tile(u32);
+    struct Selected;
+    #[derive(Debug, PartialEq)]
+    struct Reserved(u32);
+
+    #[test]
+    fn toggling_a_sparse_component_keeps_the_table_row() {
+        let mut world = World::default();
+        assert!(world.set_storage_type::<Selected>(StorageType::SparseSet));
+
+        let tiles = (0..3)
+            .map(|index| world.create_entity().with_component(Tile(index)).spawn())
+            .collect::<Vec<_>>();
+
+        world.insert_component(tiles[1].id, Selected);
+        assert!(world.has_component::<Selected>(tiles[1].id));
+        assert_eq!(world.entity_index[&tiles[1].id], tiles[1]);
+        assert_eq!(world.archetypes.len(), 1);
+
+        assert!(world.remove_component::<Selected>(tiles[1].id).is_some());
+        assert!(!world.has_component::<Selected>(tiles[1].id));
+        assert_eq!(world.entity_index[&tiles[1].id], tiles[1]);
+    }
+
+    #[test]
+    fn queries_join_sparse_and_table_components() {
+        let mut world = World::default();
+        world.set_storage_type::<Reserved>(StorageType::SparseSet);
+
+        let tiles = (0..4)
+            .map(|index| world.create_entity().with_component(Tile(index)).spawn().id)
+            .collect::<Vec<_>>();
+        world.create_entity().with_component(Reserved(10)).spawn();
+
+        world.insert_component(tiles[1], Reserved(1));
+        world.insert_component(tiles[3], Reserved(3));
+
+        let reserved_tiles = world
+            .query()
+            .with_component::<Tile>()
+            .with_component::<Reserved>()
+            .iter(&world)
+            .map(|result| {
+                assert_eq!(
+                    result.get::<Tile>().unwrap().0,
+                    result.get::<Reserved>().unwrap().0
+                );
+                result.get::<Tile>().unwrap().0
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(reserved_tiles, vec![1, 3]);
+
+        let mut reserved = world
+            .query()
+            .with_component::<Reserved>()
+            .iter(&world)
+            .map(|result| result.get::<Reserved>().unwrap().0)
+            .collect::<Vec<_>>();
+        reserved.sort();
+        assert_eq!(reserved, vec![1, 3, 10]);
+
+        world.get_component_mut::<Reserved>(tiles[3]).unwrap().0 = 30;
+        assert_eq!(
+            world.get_component::<Reserved>(tiles[3]),
+            Some(&Reserved(30))
+        );
+    }
+
+    #[test]
+    fn despawn_removes_sparse_components() {
+        let mut world = World::default();
+        world.set_storage_type::<Reserved>(StorageType::SparseSet);
+
+        let first = world
+            .create_entity()
+            .with_component(Tile(0))
+            .with_component(Reserved(0))
+            .spawn()
+            .id;
+        let second = world.create_entity().with_component(Tile(1)).spawn().id;
+        world.insert_component(second, Reserved(1));
+
+        world.despawn(first);
+
+        assert!(!world.has_component::<Reserved>(first));
+        assert_eq!(world.get_component::<Reserved>(second), Some(&Reserved(1)));
+    }
+
+    #[test]
+    fn storage_type_only_changes_while_unused() {
+        let mut world = World::default();
+
+        let tile = world.create_entity().with_component(Tile(0)).spawn().id;
+        assert!(!world.set_storage_type::<Tile>(StorageType::SparseSet));
+        assert_eq!(world.storage_type::<Tile>(), StorageType::Table);
+
+        world.set_storage_type::<Reserved>(StorageType::SparseSet);
+        world.insert_component(tile, Reserved(0));
+        assert!(!world.set_storage_type::<Reserved>(StorageType::Table));
+
+        world.remove_component::<Reserved>(tile);
+        assert!(world.set_storage_type::<Reserved>(StorageType::Table));
+        assert_eq!(world.storage_type::<Reserved>(), StorageType::Table);
+    }
+}
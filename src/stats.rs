@@ -0,0 +1,271 @@
+use crate::{AnyVec, ArchetypeId, World};
+use std::{collections::BTreeMap, fmt};
+
+/// A snapshot of how many entities the world stores and how much memory they take.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct WorldStats {
+    pub archetypes: Vec<ArchetypeStats>,
+    /// Every component type, sorted by name.
+    pub components: Vec<ComponentStats>,
+    pub resources: usize,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ArchetypeStats {
+    pub id: ArchetypeId,
+    pub entities: usize,
+    pub columns: Vec<ColumnStats>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ColumnStats {
+    pub component: String,
+    pub len: usize,
+    /// `usize::MAX` for zero sized components, which never allocate.
+    pub capacity: usize,
+    /// Bytes allocated for the column, including the unused capacity.
+    pub bytes: usize,
+}
+
+/// The memory taken by one component type, summed over every archetype and its sparse set.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ComponentStats {
+    pub component: String,
+    pub len: usize,
+    pub bytes: usize,
+}
+
+impl ColumnStats {
+    fn new(component: String, components: &AnyVec) -> Self {
+        Self {
+            component,
+            len: components.len(),
+            capacity: components.capacity(),
+            bytes: components.allocated_bytes(),
+        }
+    }
+}
+
+impl WorldStats {
+    /// Bytes allocated for component values, in tables and sparse sets.
+    pub fn total_bytes(&self) -> usize {
+        self.components
+            .iter()
+            .map(|component| component.bytes)
+            .sum()
+    }
+}
+
+impl World {
+    pub fn stats(&self) -> WorldStats {
+        let archetypes = self
+            .archetypes
+            .iter()
+            .enumerate()
+            .filter(|(id, _)| !self.free_archetypes.contains(id))
+            .map(|(id, archetype)| {
+                let mut columns = archetype
+                    .column_index
+                    .iter()
+                    .map(|(component_id, column_id)| {
+                        ColumnStats::new(
+                            self.component_name(*component_id),
+                            &archetype.columns[*column_id].components,
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                columns.sort_by(|a, b| a.component.cmp(&b.component));
+
+                ArchetypeStats {
+                    id,
+                    entities: archetype.entities.len(),
+                    columns,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let sparse = self.sparse_sets.iter().map(|(component_id, sparse_set)| {
+            ColumnStats::new(self.component_name(*component_id), sparse_set.values())
+        });
+
+        let mut components = BTreeMap::<String, ComponentStats>::new();
+        archetypes
+            .iter()
+            .flat_map(|archetype| archetype.columns.iter().cloned())
+            .chain(sparse)
+            .for_each(|column| {
+                let component = components
+                    .entry(column.component.clone())
+                    .or_insert_with(|| ComponentStats {
+                        component: column.component,
+                        len: 0,
+                        bytes: 0,
+                    });
+                component.len += column.len;
+                component.bytes += column.bytes;
+            });
+
+        WorldStats {
+            archetypes,
+            components: components.into_values().collect(),
+            resources: self.resources.len(),
+        }
+    }
+}
+
+impl fmt::Display for WorldStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .components
+            .iter()
+            .map(|component| component.component.len())
+            .max()
+            .unwrap_or(0)
+            .max("component".len());
+
+        writeln!(
+            f,
+            "{:>9} {:>8}  {:<width$} {:>8} {:>8} {:>10}",
+            "archetype", "entities", "component", "len", "capacity", "bytes"
+        )?;
+        for archetype in &self.archetypes {
+            write!(f, "{:>9} {:>8}", archetype.id, archetype.entities)?;
+
+            if archetype.columns.is_empty() {
+                writeln!(f)?;
+            }
+
+            for (index, column) in archetype.columns.iter().enumerate() {
+                if index > 0 {
+                    write!(f, "{:>9} {:>8}", "", "")?;
+                }
+
+                let capacity = match column.capacity {
+                    usize::MAX => "-".to_string(),
+                    capacity => capacity.to_string(),
+                };
+                writeln!(
+                    f,
+                    "  {:<width$} {:>8} {:>8} {:>10}",
+                    column.component, column.len, capacity, column.bytes
+                )?;
+            }
+        }
+
+        writeln!(f)?;
+        writeln!(f, "{:<width$} {:>8} {:>10}", "component", "len", "bytes")?;
+        for component in &self.components {
+            writeln!(
+                f,
+                "{:<width$} {:>8} {:>10}",
+                component.component, component.len, component.bytes
+            )?;
+        }
+        writeln!(
+            f,
+            "{:<width$} {:>8} {:>10}",
+            "total",
+            "",
+            self.total_bytes()
+        )?;
+
+        writeln!(f)?;
+        write!(f, "resources: {}", self.resources)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StorageType;
+    use std::any::type_name;
+
+    struct Tile(u32);
+    struct Selected;
+    struct Reserved(u64);
+    struct OccupiedBy;
+    struct TileSize(u32);
+
+    #[test]
+    fn stats_report_columns_and_totals() {
+        let mut world = World::default();
+        world.set_storage_type::<Reserved>(StorageType::SparseSet);
+        world.add_resource(TileSize(16));
+
+        let tiles = (0..3)
+            .map(|index| world.create_entity().with_component(Tile(index)).spawn().id)
+            .collect::<Vec<_>>();
+        world.insert_component(tiles[0], Selected);
+        world.insert_component(tiles[1], Reserved(1));
+        world.insert_pair(tiles[2], OccupiedBy, tiles[0]);
+
+        let stats = world.stats();
+        assert_eq!(world.get_component::<Tile>(tiles[2]).unwrap().0, 2);
+        assert_eq!(world.get_component::<Reserved>(tiles[1]).unwrap().0, 1);
+        assert_eq!(world.get_resource::<TileSize>().unwrap().0, 16);
+
+        assert_eq!(stats.resources, 1);
+        assert_eq!(stats.archetypes.len(), 3);
+
+        let tiles_only = &stats.archetypes[0];
+        assert_eq!(tiles_only.entities, 1);
+        assert_eq!(
+            tiles_only.columns,
+            vec![ColumnStats {
+                component: type_name::<Tile>().to_string(),
+                len: 1,
+                capacity: 4,
+                bytes: 16,
+            }]
+        );
+
+        let selected = stats.archetypes[1]
+            .columns
+            .iter()
+            .find(|column| column.component == type_name::<Selected>())
+            .unwrap();
+        assert_eq!(
+            (selected.len, selected.capacity, selected.bytes),
+            (1, usize::MAX, 0)
+        );
+
+        let pair = format!("({}, {})", type_name::<OccupiedBy>(), tiles[0]);
+        assert!(stats.archetypes[2]
+            .columns
+            .iter()
+            .any(|column| column.component == pair));
+
+        let tile = stats
+            .components
+            .iter()
+            .find(|component| component.component == type_name::<Tile>())
+            .unwrap();
+        assert_eq!(tile.len, 3);
+
+        let reserved = stats
+            .components
+            .iter()
+            .find(|component| component.component == type_name::<Reserved>())
+            .unwrap();
+        assert_eq!((reserved.len, reserved.bytes), (1, 8));
+
+        assert_eq!(
+            stats.total_bytes(),
+            stats.components.iter().map(|c| c.bytes).sum::<usize>()
+        );
+    }
+
+    #[test]
+    fn stats_print_as_a_table() {
+        let mut world = World::default();
+        world.create_entity().with_component(Tile(0)).spawn();
+
+        let table = world.stats().to_string();
+        let lines = table.lines().collect::<Vec<_>>();
+
+        assert!(lines[0].starts_with("archetype entities  component"));
+        assert!(lines[1].contains(type_name::<Tile>()));
+        assert!(lines.iter().any(|line| line.starts_with("total")));
+        assert_eq!(lines.last(), Some(&"resources: 0"));
+    }
+}
@@ -0,0 +1,337 @@
+use crate::{AnyVec, Children, ComponentId, EntityCreator, EntityId, Parent, World};
+use std::collections::HashMap;
+
+impl World {
+    /// Moves `entity` with its components into `other` and returns its id there.
+    ///
+    /// Like with [`World::extend_from`], Rust components only move if their types are registered
+    /// with [`World::register_clone`] in both worlds, and dynamic components if `other` has
+    /// registered one with the same name and layout. Relation pairs and the hierarchy refer to
+    /// entities of this world, so they are dropped, as is anything else that can't move.
+    pub fn move_entity_to(&mut self, other: &mut World, entity: EntityId) -> Option<EntityId> {
+        if !self.contains(entity) {
+            return None;
+        }
+
+        let components = self.deferred(|world| {
+            world.detach_hierarchy(entity);
+
+            let (moved, dropped): (Vec<_>, Vec<_>) = world
+                .component_ids(entity)
+                .into_iter()
+                .map(|component_id| {
+                    let counterpart = world.counterpart(other, component_id, &HashMap::new());
+                    (component_id, counterpart)
+                })
+                .partition(|(_, counterpart)| counterpart.is_some());
+
+            dropped.into_iter().for_each(|(component_id, _)| {
+                world.drop_component(entity, component_id);
+            });
+
+            let components = moved
+                .into_iter()
+                .map(|(component_id, counterpart)| {
+                    let mut column = world.column_of(entity, component_id).empty_like();
+                    let component = world.get_component_raw(entity, component_id).unwrap();
+                    unsafe { column.push_raw(component) };
+
+                    other.copy_type_name(world, component_id);
+                    (counterpart.unwrap(), column)
+                })
+                .collect::<Vec<_>>();
+
+            // The values now belong to the columns above, so they must be forgotten here.
+            world.despawn_entity(entity, false);
+            components
+        });
+
+        Some(other.spawn_columns(components))
+    }
+
+    /// Spawns a copy of every entity of `other` and returns the id each one got in this world.
+    ///
+    /// Only Rust components whose types are registered with [`World::register_clone`] in both
+    /// worlds are copied, along with dynamic components registered in both worlds under the same
+    /// name and layout, as long as they have no drop function. Relation pairs and the hierarchy
+    /// are remapped to the new entities. [`World::move_entity_to`] follows the same rules.
+    pub fn extend_from(&mut self, other: &World) -> HashMap<EntityId, EntityId> {
+        let mut sources = other.entity_index.keys().copied().collect::<Vec<_>>();
+        sources.sort_unstable();
+
+        self.deferred(|world| {
+            let mut entities = HashMap::new();
+
+            sources.iter().for_each(|source| {
+                let components = other
+                    .component_ids(*source)
+                    .into_iter()
+                    .filter(|component_id| !matches!(component_id, ComponentId::Pair(..)))
+                    .filter_map(|component_id| world.copy_from(other, *source, component_id))
+                    .collect();
+
+                entities.insert(*source, world.spawn_columns(components));
+            });
+
+            // Pairs are added once every entity exists, so their targets can be remapped.
+            sources.iter().for_each(|source| {
+                other
+                    .component_ids(*source)
+                    .into_iter()
+                    .filter(|component_id| matches!(component_id, ComponentId::Pair(..)))
+                    .for_each(|component_id| {
+                        let Some((counterpart, mut component)) =
+                            world.copy_from_with(other, *source, component_id, &entities)
+                        else {
+                            return;
+                        };
+
+                        let column = component.empty_like();
+                        unsafe {
+                            world.insert_component_with_column(
+                                entities[source],
+                                counterpart,
+                                component.get_raw(0).unwrap(),
+                                column,
+                            );
+                            component.set_len(0);
+                        }
+                    });
+            });
+
+            sources.iter().for_each(|source| {
+                other.children(*source).iter().for_each(|child| {
+                    world.set_parent(entities[child], entities[source]);
+                });
+            });
+
+            entities
+        })
+    }
+
+    /// The id `component_id` of this world has in `other`, with pair targets remapped through
+    /// `entities`. `None` if the component can't exist there, which for Rust types means either
+    /// world hasn't registered them with [`World::register_clone`].
+    fn counterpart(
+        &self,
+        other: &World,
+        component_id: ComponentId,
+        entities: &HashMap<EntityId, EntityId>,
+    ) -> Option<ComponentId> {
+        match component_id {
+            ComponentId::Type(_)
+                if component_id == ComponentId::of::<Parent>()
+                    || component_id == ComponentId::of::<Children>() =>
+            {
+                None
+            }
+            ComponentId::Type(type_id) | ComponentId::Pair(type_id, _)
+                if !self.clone_fns.contains_key(&type_id)
+                    || !other.clone_fns.contains_key(&type_id) =>
+            {
+                None
+            }
+            ComponentId::Type(_) => Some(component_id),
+            ComponentId::Pair(relation, target) => {
+                Some(ComponentId::Pair(relation, *entities.get(&target)?))
+            }
+            ComponentId::Dynamic(_) => {
+                let info = self.component_info(component_id)?;
+                let index = other.dynamic_components.iter().position(|other_info| {
+                    other_info.name() == info.name() && other_info.layout() == info.layout()
+                })?;
+
+                Some(ComponentId::Dynamic(index))
+            }
+        }
+    }
+
+    /// A copy of the `component_id` value of `entity` in `other`, along with the id it has in
+    /// this world. `None` if it can't be copied.
+    fn copy_from(
+        &mut self,
+        other: &World,
+        entity: EntityId,
+        component_id: ComponentId,
+    ) -> Option<(ComponentId, AnyVec)> {
+        self.copy_from_with(other, entity, component_id, &HashMap::new())
+    }
+
+    fn copy_from_with(
+        &mut self,
+        other: &World,
+        entity: EntityId,
+        component_id: ComponentId,
+        entities: &HashMap<EntityId, EntityId>,
+    ) -> Option<(ComponentId, AnyVec)> {
+        let counterpart = other.counterpart(self, component_id, entities)?;
+        let component = other.get_component_raw(entity, component_id)?;
+        let mut column = other.column_of(entity, component_id).empty_like();
+
+        match component_id {
+            ComponentId::Type(type_id) | ComponentId::Pair(type_id, _) => {
+                let clone = other.clone_fns[&type_id];
+                unsafe { clone(component, &mut column) };
+            }
+            ComponentId::Dynamic(_) => {
+                // Values with a drop function may own resources, so their bytes can't be shared.
+                if other.component_info(component_id)?.drop_fn().is_some() {
+                    return None;
+                }
+
+                unsafe { column.push_raw(component) };
+            }
+        }
+
+        self.copy_type_name(other, component_id);
+        Some((counterpart, column))
+    }
+
+    /// The storage holding the `component_id` value of `entity`.
+    pub(crate) fn column_of(&self, entity: EntityId, component_id: ComponentId) -> &AnyVec {
+        if let Some(sparse_set) = self.sparse_sets.get(&component_id) {
+            return sparse_set.values();
+        }
+
+        let archetype = &self.archetypes[self.entity_index[&entity].archetype_id];
+        &archetype.columns[archetype.column_index[&component_id]].components
+    }
+
+    fn copy_type_name(&mut self, other: &World, component_id: ComponentId) {
+        if let ComponentId::Type(type_id) | ComponentId::Pair(type_id, _) = component_id {
+            if let Some(name) = other.type_names.get(&type_id) {
+                self.type_names.insert(type_id, name);
+            }
+        }
+    }
+
+    /// Spawns an entity out of single-value columns.
+    fn spawn_columns(&mut self, components: Vec<(ComponentId, AnyVec)>) -> EntityId {
+        let (sparse_components, components): (Vec<_>, Vec<_>) = components
+            .into_iter()
+            .partition(|(component_id, _)| self.sparse_sets.contains_key(component_id));
+
+        let creator = EntityCreator {
+            world: self,
+            components: components.into_iter().collect(),
+            sparse_components: sparse_components.into_iter().collect(),
+        };
+
+        creator.spawn().id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StorageType;
+    use std::{alloc::Layout, sync::Arc};
+
+    #[derive(Debug, PartialEq, Clone)]
+    struct Rail(u32);
+    #[derive(Debug, PartialEq, Clone)]
+    struct Reserved;
+    #[derive(Debug, PartialEq, Clone)]
+    struct ConnectedTo;
+    struct Preview;
+
+    #[test]
+    fn moved_entities_keep_their_values() {
+        let mut preview = World::default();
+        let mut simulation = World::default();
+        [&mut preview, &mut simulation]
+            .into_iter()
+            .for_each(|world| {
+                world.register_clone::<Rail>();
+                world.register_clone::<Reserved>();
+                world.register_clone::<Arc<()>>();
+            });
+        preview.register_clone::<ConnectedTo>();
+        preview.set_storage_type::<Reserved>(StorageType::SparseSet);
+        let preview_fuel = preview.register_dynamic_component("fuel", Layout::new::<u32>(), None);
+        let preview_cargo = preview.register_dynamic_component("cargo", Layout::new::<u8>(), None);
+        simulation.register_dynamic_component("other", Layout::new::<u64>(), None);
+        let fuel = simulation.register_dynamic_component("fuel", Layout::new::<u32>(), None);
+
+        let counter = Arc::new(());
+        let parent = preview.create_entity().spawn().id;
+        let entity = unsafe {
+            preview
+                .create_entity()
+                .with_component(Rail(3))
+                .with_component(Reserved)
+                .with_component(counter.clone())
+                .with_component(Preview)
+                .with_pair(ConnectedTo, parent)
+                .with_component_raw(preview_fuel, &7u32 as *const u32 as *const u8)
+                .with_component_raw(preview_cargo, &1u8)
+                .spawn()
+                .id
+        };
+        preview.set_parent(entity, parent);
+        simulation.create_entity().spawn();
+
+        let moved = preview.move_entity_to(&mut simulation, entity).unwrap();
+
+        assert!(!preview.contains(entity));
+        assert!(preview.children(parent).is_empty());
+        assert_eq!(Arc::strong_count(&counter), 2);
+        assert_eq!(simulation.get_component::<Rail>(moved), Some(&Rail(3)));
+        assert_eq!(simulation.get_component::<Reserved>(moved), Some(&Reserved));
+        assert!(!simulation.has_component::<Preview>(moved));
+        assert_eq!(simulation.parent(moved), None);
+        assert!(simulation.targets::<ConnectedTo>(moved).is_empty());
+        let component = simulation.get_component_raw(moved, fuel).unwrap();
+        assert_eq!(unsafe { *(component as *const u32) }, 7);
+        assert_eq!(simulation.component_ids(moved).len(), 4);
+
+        drop(simulation);
+        assert_eq!(Arc::strong_count(&counter), 1);
+        assert!(preview
+            .move_entity_to(&mut World::default(), entity)
+            .is_none());
+    }
+
+    #[test]
+    fn extend_from_remaps_relations_and_hierarchy() {
+        let mut preview = World::default();
+        let mut simulation = World::default();
+        [&mut preview, &mut simulation]
+            .into_iter()
+            .for_each(|world| {
+                world.register_clone::<Rail>();
+                world.register_clone::<ConnectedTo>();
+            });
+        preview.register_clone::<Reserved>();
+
+        simulation.create_entity().with_component(Rail(0)).spawn();
+
+        let station = preview.create_entity().with_component(Rail(1)).spawn().id;
+        let rail = preview
+            .create_entity()
+            .with_component(Rail(2))
+            .with_component(Reserved)
+            .with_component(Preview)
+            .with_pair(ConnectedTo, station)
+            .spawn()
+            .id;
+        preview.set_parent(rail, station);
+
+        let entities = simulation.extend_from(&preview);
+
+        let (new_station, new_rail) = (entities[&station], entities[&rail]);
+        assert_ne!(new_station, station);
+        assert_eq!(simulation.get_component::<Rail>(new_rail), Some(&Rail(2)));
+        assert!(!simulation.has_component::<Reserved>(new_rail));
+        assert!(!simulation.has_component::<Preview>(new_rail));
+        assert_eq!(
+            simulation.targets::<ConnectedTo>(new_rail),
+            vec![new_station]
+        );
+        assert_eq!(simulation.parent(new_rail), Some(new_station));
+        assert_eq!(simulation.children(new_station), &[new_rail]);
+
+        assert_eq!(preview.get_component::<Rail>(rail), Some(&Rail(2)));
+        assert_eq!(preview.targets::<ConnectedTo>(rail), vec![station]);
+    }
+}